@@ -0,0 +1,196 @@
+// Âm thanh: đọc clip WAV/OGG ngay trong chương trình và phát bằng trình phát
+// của hệ thống (aplay, afplay hoặc PowerShell)
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+// Clip đã giải mã: mẫu 16-bit xen kẽ các kênh
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub name: String,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+impl Clip {
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let extension = path.extension().unwrap_or_default().to_string_lossy().to_ascii_lowercase();
+        let (channels, sample_rate, samples) = match extension.as_str() {
+            "wav" => {
+                let mut reader = hound::WavReader::open(path)?;
+                let spec = reader.spec();
+                let samples = match (spec.sample_format, spec.bits_per_sample) {
+                    (hound::SampleFormat::Int, 16) => reader.samples::<i16>().collect::<Result<_, _>>()?,
+                    (hound::SampleFormat::Int, bits) => {
+                        let shift = bits as i32 - 16;
+                        reader
+                            .samples::<i32>()
+                            .map(|s| s.map(|s| if shift > 0 { s >> shift } else { s << -shift } as i16))
+                            .collect::<Result<_, _>>()?
+                    }
+                    (hound::SampleFormat::Float, _) => reader
+                        .samples::<f32>()
+                        .map(|s| s.map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16))
+                        .collect::<Result<_, _>>()?,
+                };
+                (spec.channels, spec.sample_rate, samples)
+            }
+            "ogg" => {
+                let file = std::fs::File::open(path)?;
+                let mut reader = lewton::inside_ogg::OggStreamReader::new(file)?;
+                let mut samples = Vec::new();
+                while let Some(packet) = reader.read_dec_packet_itl()? {
+                    samples.extend(packet);
+                }
+                (reader.ident_hdr.audio_channels as u16, reader.ident_hdr.audio_sample_rate, samples)
+            }
+            _ => return Err(anyhow!("{}: only WAV and OGG sounds are supported", path.display())),
+        };
+        if channels == 0 || sample_rate == 0 {
+            return Err(anyhow!("{}: invalid audio header", path.display()));
+        }
+
+        Ok(Self { name, channels, sample_rate, samples })
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.channels as f64 / self.sample_rate as f64)
+    }
+
+    pub fn write_wav(&self, path: &Path, volume: f32) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for sample in &self.samples {
+            writer.write_sample((*sample as f32 * volume) as i16)?;
+        }
+        writer.finalize()?;
+        Ok(())
+    }
+}
+
+pub trait AudioOutput: Send {
+    fn play(&mut self, clip: &Clip, volume: f32) -> Result<()>;
+}
+
+// Ghi clip ra file WAV tạm rồi chạy trình phát, có thể phát chồng nhau
+pub struct PlayerProcess {
+    command: Vec<String>,
+    playing: Vec<(Child, PathBuf)>,
+    next: u64,
+}
+
+impl PlayerProcess {
+    pub fn new() -> Self {
+        Self {
+            command: player_command(),
+            playing: Vec::new(),
+            next: 0,
+        }
+    }
+
+    // Dọn các clip đã phát xong
+    fn reap(&mut self) {
+        self.playing.retain_mut(|(child, path)| {
+            if matches!(child.try_wait(), Ok(None)) {
+                return true;
+            }
+            let _ = std::fs::remove_file(path);
+            false
+        });
+    }
+}
+
+impl AudioOutput for PlayerProcess {
+    fn play(&mut self, clip: &Clip, volume: f32) -> Result<()> {
+        self.reap();
+
+        self.next += 1;
+        let path = std::env::temp_dir().join(format!("pycatai-sound-{}-{}.wav", std::process::id(), self.next));
+        clip.write_wav(&path, volume)?;
+        let child = spawn_player(&self.command, &path)?;
+        self.playing.push((child, path));
+        Ok(())
+    }
+}
+
+impl Drop for PlayerProcess {
+    fn drop(&mut self) {
+        for (child, path) in &mut self.playing {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Ghi lại tên các clip đã phát, cho test
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingOutput {
+    pub played: std::sync::Arc<std::sync::Mutex<Vec<(String, f32)>>>,
+}
+
+#[cfg(test)]
+impl RecordingOutput {
+    pub fn played(&self) -> Vec<(String, f32)> {
+        self.played.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl AudioOutput for RecordingOutput {
+    fn play(&mut self, clip: &Clip, volume: f32) -> Result<()> {
+        self.played.lock().unwrap().push((clip.name.clone(), volume));
+        Ok(())
+    }
+}
+
+// Lệnh phát một file WAV; đường dẫn file được thêm vào cuối
+pub fn player_command() -> Vec<String> {
+    let command: &[&str] = if cfg!(windows) {
+        &["powershell", "-NoProfile", "-Command", "(New-Object Media.SoundPlayer $args[0]).PlaySync()"]
+    } else if cfg!(target_os = "macos") {
+        &["afplay"]
+    } else {
+        &["aplay", "-q"]
+    };
+    command.iter().map(|part| part.to_string()).collect()
+}
+
+pub fn spawn_player(command: &[String], path: &Path) -> Result<Child> {
+    let (program, args) = command.split_first().ok_or_else(|| anyhow!("No audio player"))?;
+    Command::new(program)
+        .args(args)
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Cannot run audio player {}", program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_roundtrip_with_volume() {
+        let path = std::env::temp_dir().join(format!("pycatai-clip-{}.wav", std::process::id()));
+        let clip = Clip { name: "beep".to_string(), channels: 2, sample_rate: 8000, samples: vec![1000, -1000, 400, -400] };
+        clip.write_wav(&path, 0.5).unwrap();
+
+        let loaded = Clip::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.name, format!("pycatai-clip-{}", std::process::id()));
+        assert_eq!((loaded.channels, loaded.sample_rate), (2, 8000));
+        assert_eq!(loaded.samples, vec![500, -500, 200, -200]);
+        assert_eq!(loaded.duration(), Duration::from_micros(250));
+    }
+}
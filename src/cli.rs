@@ -0,0 +1,265 @@
+// Tham số dòng lệnh
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::{Config, Provider};
+use crate::window_handler::WindowRect;
+
+#[derive(Debug, Parser)]
+#[command(name = "pycatai-pet", version, about = "An AI powered desktop pet")]
+pub struct Cli {
+    /// Config file to use instead of the one in the platform config directory
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sprite pack directory, one sub-directory per animation
+    #[arg(long, value_name = "DIR")]
+    pub sprites: Option<PathBuf>,
+
+    /// Built-in persona name or path to a prompt file
+    #[arg(long)]
+    pub persona: Option<String>,
+
+    /// AI provider used to generate comments
+    #[arg(long, value_enum)]
+    pub provider: Option<Provider>,
+
+    /// Never touch the network; same as `--provider offline`
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Disable text-to-speech
+    #[arg(long)]
+    pub no_tts: bool,
+
+    /// Turn off the cat's sound effects
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Seed for the random number generators, for reproducible runs
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Log level (error, warn, info, debug, trace); overrides RUST_LOG
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<log::LevelFilter>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print one comment about the screen (or an image file) and exit
+    CommentOnce {
+        /// Comment on this image instead of a screenshot
+        #[arg(long, value_name = "FILE")]
+        image: Option<PathBuf>,
+    },
+    /// Check that a sprite pack has every animation and readable frames
+    ValidateSprites {
+        dir: PathBuf,
+    },
+    /// List the voices offered by the system TTS
+    ListVoices,
+    /// Open the settings window; the running cat reloads the saved config
+    Settings,
+    /// Send a command to the running cat (needs `enabled = true` under [control])
+    Control {
+        #[command(subcommand)]
+        command: ControlCommand,
+    },
+    /// Run COMMAND, then tell the running cat whether it passed; exits with COMMAND's exit code
+    Notify {
+        /// The command and its arguments, e.g. `pycatai-pet notify -- cargo build`
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Run the cat without a window and print a JSON line per step
+    Simulate {
+        /// Simulated time to run
+        #[arg(long, default_value_t = 60.0)]
+        seconds: f64,
+        /// Work area of the simulated screen
+        #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "1920x1080", value_parser = parse_screen)]
+        screen: WindowRect,
+        /// A simulated window, front-most first; may be repeated
+        #[arg(long = "window", value_name = "LEFT,TOP,RIGHT,BOTTOM", value_parser = parse_window)]
+        windows: Vec<WindowRect>,
+    },
+}
+
+// Lệnh cho con mèo đang chạy, cũng là phần "command" của API điều khiển
+#[derive(Debug, Clone, PartialEq, Subcommand, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ControlCommand {
+    /// Show TEXT in the chat bubble and read it aloud
+    Say {
+        text: String,
+    },
+    /// Play an animation from the sprite pack until the cat does something else
+    Play {
+        animation: String,
+    },
+    /// Put the cat to sleep for the snooze time; no comments until then
+    Sleep,
+    /// Wake the cat up and end the snooze
+    Wake,
+    /// Comment on the screen now
+    CommentNow,
+    /// Switch persona until config.toml changes
+    SetPersona {
+        persona: String,
+    },
+    /// Tell the cat that COMMAND finished, so it celebrates or despairs
+    Finished {
+        command: String,
+        /// Leave out when the command was killed by a signal
+        #[arg(long)]
+        exit_code: Option<i32>,
+    },
+    /// Print the cat's state as JSON
+    Status,
+}
+
+fn parse_screen(value: &str) -> Result<WindowRect, String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got '{}'", value))?;
+    let width: i32 = width.trim().parse().map_err(|e| format!("invalid width: {}", e))?;
+    let height: i32 = height.trim().parse().map_err(|e| format!("invalid height: {}", e))?;
+    if width <= 0 || height <= 0 {
+        return Err("screen size must be positive".to_string());
+    }
+    Ok(WindowRect::new(0, 0, width, height))
+}
+
+fn parse_window(value: &str) -> Result<WindowRect, String> {
+    let parts = value
+        .split(',')
+        .map(|part| part.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid window '{}': {}", value, e))?;
+    match parts.as_slice() {
+        [left, top, right, bottom] if right > left && bottom > top => Ok(WindowRect::new(*left, *top, *right, *bottom)),
+        [_, _, _, _] => Err(format!("window '{}' has no area", value)),
+        _ => Err(format!("expected LEFT,TOP,RIGHT,BOTTOM, got '{}'", value)),
+    }
+}
+
+// Giá trị từ dòng lệnh ghi đè lên file cấu hình, kể cả sau khi nạp lại
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    sprites: Option<PathBuf>,
+    persona: Option<String>,
+    provider: Option<Provider>,
+    no_tts: bool,
+    mute: bool,
+}
+
+impl Cli {
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            sprites: self.sprites.clone(),
+            persona: self.persona.clone(),
+            provider: if self.offline { Some(Provider::Offline) } else { self.provider },
+            no_tts: self.no_tts,
+            mute: self.mute,
+        }
+    }
+}
+
+impl Overrides {
+    pub fn persona(persona: String) -> Self {
+        Self { persona: Some(persona), ..Default::default() }
+    }
+
+    pub fn sprites(dir: PathBuf) -> Self {
+        Self { sprites: Some(dir), ..Default::default() }
+    }
+
+    // Tham số chuyển từ lần chạy sau cộng dồn vào tham số đang dùng
+    pub fn merge(&mut self, other: Overrides) {
+        self.sprites = other.sprites.or(self.sprites.take());
+        self.persona = other.persona.or(self.persona.take());
+        self.provider = other.provider.or(self.provider);
+        self.no_tts |= other.no_tts;
+        self.mute |= other.mute;
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(sprites) = &self.sprites {
+            config.display.sprite_dir = sprites.clone();
+        }
+        if let Some(persona) = &self.persona {
+            config.comments.persona = persona.clone();
+        }
+        if let Some(provider) = self.provider {
+            config.comments.provider = provider;
+        }
+        if self.no_tts {
+            config.comments.tts = false;
+        }
+        if self.mute {
+            config.sounds.enabled = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_win_over_config() {
+        let cli = Cli::parse_from(["pycatai-pet", "--offline", "--provider", "openai", "--no-tts", "--mute", "--persona", "kitten"]);
+        let mut config = Config::default();
+        cli.overrides().apply(&mut config);
+
+        assert_eq!(config.comments.provider, Provider::Offline);
+        assert!(!config.comments.tts);
+        assert!(!config.sounds.enabled);
+        assert_eq!(config.comments.persona, "kitten");
+        assert_eq!(config.display.sprite_dir, Config::default().display.sprite_dir);
+
+        let mut overrides = cli.overrides();
+        overrides.merge(Cli::parse_from(["pycatai-pet", "--persona", "grumpy"]).overrides());
+        let mut config = Config::default();
+        overrides.apply(&mut config);
+        assert_eq!(config.comments.persona, "grumpy");
+        assert!(!config.sounds.enabled);
+    }
+
+    #[test]
+    fn test_subcommands_parse() {
+        let cli = Cli::parse_from(["pycatai-pet", "--seed", "42", "comment-once", "--image", "cat.png"]);
+        assert_eq!(cli.seed, Some(42));
+        assert!(matches!(cli.command, Some(Command::CommentOnce { image: Some(_) })));
+
+        let cli = Cli::parse_from(["pycatai-pet", "validate-sprites", "sprites"]);
+        assert!(matches!(cli.command, Some(Command::ValidateSprites { .. })));
+
+        let cli = Cli::parse_from(["pycatai-pet", "simulate", "--screen", "1280x720", "--window", "10,20,300,400"]);
+        match cli.command {
+            Some(Command::Simulate { screen, windows, .. }) => {
+                assert_eq!(screen, WindowRect::new(0, 0, 1280, 720));
+                assert_eq!(windows, vec![WindowRect::new(10, 20, 300, 400)]);
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["pycatai-pet", "simulate", "--window", "10,20,5,400"]).is_err());
+
+        // Lệnh điều khiển và dạng JSON mà script gửi
+        let cli = Cli::parse_from(["pycatai-pet", "control", "say", "Build finished"]);
+        let Some(Command::Control { command }) = cli.command else {
+            panic!("unexpected command {:?}", cli.command);
+        };
+        assert_eq!(command, ControlCommand::Say { text: "Build finished".to_string() });
+        assert_eq!(serde_json::to_string(&command).unwrap(), r#"{"say":{"text":"Build finished"}}"#);
+        assert_eq!(serde_json::from_str::<ControlCommand>(r#""comment-now""#).unwrap(), ControlCommand::CommentNow);
+
+        let cli = Cli::parse_from(["pycatai-pet", "notify", "--", "cargo", "test", "--release"]);
+        assert!(matches!(cli.command, Some(Command::Notify { command }) if command == ["cargo", "test", "--release"]));
+    }
+}
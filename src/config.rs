@@ -0,0 +1,735 @@
+// Cấu hình của mèo: một file TOML trong thư mục config của hệ điều hành,
+// được kiểm tra hợp lệ và tự nạp lại khi file thay đổi
+use anyhow::{anyhow, Context, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::comment_generator;
+use crate::events::EventKind;
+use crate::screen_source;
+use crate::speech;
+use crate::timing::{CommentSchedule, TimeRange};
+
+pub const APP_DIR: &str = "pycatai-pet";
+// Giới hạn của comments.http_timeout_secs
+pub const MAX_HTTP_TIMEOUT_SECS: f64 = 300.0;
+const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub display: DisplayConfig,
+    pub movement: MovementConfig,
+    pub comments: CommentConfig,
+    pub speech: SpeechConfig,
+    pub sounds: SoundConfig,
+    pub quiet: QuietConfig,
+    pub privacy: PrivacyConfig,
+    pub events: EventConfig,
+    pub logging: LoggingConfig,
+    pub control: ControlConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    // Khoảng thời gian giữa hai lần cập nhật animation
+    pub tick_ms: u64,
+    // Bật chế độ overlay khi khởi động
+    pub overlay: bool,
+    // Vị trí x ban đầu của mèo
+    pub start_x: i32,
+    // Thư mục sprite, mỗi animation một thư mục con
+    pub sprite_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MovementConfig {
+    pub step_interval_secs: f64,
+    pub step_pixels: i32,
+    // Xác suất nhảy lên cửa sổ sau mỗi bước
+    pub jump_chance: f64,
+    // Xác suất biến mất một lúc khi đi ra khỏi màn hình
+    pub disappear_chance: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommentConfig {
+    pub average_interval_secs: f64,
+    pub min_interval_secs: f64,
+    pub max_interval_secs: f64,
+    // Xác suất bình luận ngay sau khi khởi động
+    pub initial_comment_chance: f64,
+    pub http_timeout_secs: f64,
+    pub provider: Provider,
+    // API key của provider; không có thì đọc GEMINI_API_KEY / OPENAI_API_KEY
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    // Thay địa chỉ API của provider, ví dụ proxy hoặc server tương thích OpenAI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+    // Ảnh gửi cho AI: màn hình thật, file, thư mục ảnh hoặc ảnh mẫu
+    pub screen_source: ScreenSourceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_path: Option<PathBuf>,
+    // Tên persona có sẵn hoặc đường dẫn tới file prompt
+    pub persona: String,
+    pub tts: bool,
+}
+
+// Dịch vụ AI dùng để tạo bình luận
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Gemini,
+    #[value(name = "openai")]
+    #[serde(rename = "openai")]
+    OpenAi,
+    // Không gọi mạng, chỉ dùng câu có sẵn
+    Offline,
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Gemini => "gemini",
+            Self::OpenAi => "openai",
+            Self::Offline => "offline",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenSourceKind {
+    Screen,
+    File,
+    Directory,
+    Pattern,
+}
+
+impl ScreenSourceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Screen => "screen",
+            Self::File => "file",
+            Self::Directory => "directory",
+            Self::Pattern => "pattern",
+        }
+    }
+}
+
+// Giọng đọc bình luận (khi comments.tts = true)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeechConfig {
+    // Hệ số tốc độ và cao độ, 1.0 là giọng bình thường
+    pub rate: f32,
+    pub pitch: f32,
+    pub volume: f32,
+    // Tên giọng của hệ điều hành, xem `pycatai-pet list-voices`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    // Câu mới ngắt câu đang đọc hay chờ đọc xong
+    pub queue: QueuePolicy,
+    pub backend: SpeechBackendKind,
+    // Giọng Piper (cần build với --features piper)
+    pub piper_executable: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub piper_model: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub piper_speaker: Option<u32>,
+}
+
+// Giọng của hệ điều hành hoặc giọng neural Piper chạy cục bộ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechBackendKind {
+    System,
+    Piper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuePolicy {
+    Interrupt,
+    Enqueue,
+}
+
+// Tiếng mèo kêu từ thư mục sounds/ của bộ sprite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundConfig {
+    pub enabled: bool,
+    pub volume: f32,
+}
+
+// Không làm phiền: mèo vẫn chạy (và đi ngủ) nhưng không bình luận, không
+// đọc và không kêu
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuietConfig {
+    // Khung giờ yên lặng theo giờ máy, ví dụ ["22:00-07:00"]
+    pub hours: Vec<TimeRange>,
+    // Yên lặng khi cửa sổ đang focus ở chế độ toàn màn hình
+    pub fullscreen: bool,
+    // Thời gian tạm tắt khi bấm phím S trên mèo
+    pub snooze_minutes: f64,
+}
+
+// Không chụp màn hình khi cửa sổ đang focus có tiêu đề chứa một trong các
+// chuỗi này (không phân biệt hoa thường), ví dụ ["1Password", "Bank"]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivacyConfig {
+    pub deny_titles: Vec<String>,
+}
+
+// Bình luận ngay khi có chuyện xảy ra trên màn hình, ngoài lịch ngẫu nhiên.
+// Thời gian chờ tính riêng cho từng loại sự kiện.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventConfig {
+    pub enabled: bool,
+    // Chuyển sang ứng dụng khác
+    pub app_switch_cooldown_secs: f64,
+    // Tiêu đề cửa sổ đổi: tài liệu hay tab mới
+    pub title_change_cooldown_secs: f64,
+    // Ở một ứng dụng liên tục chừng này thì mèo nhắc
+    pub long_focus_secs: f64,
+    pub long_focus_cooldown_secs: f64,
+    // Không chạm bàn phím, chuột chừng này thì coi là rời máy
+    pub idle_secs: f64,
+    pub idle_cooldown_secs: f64,
+    pub return_cooldown_secs: f64,
+}
+
+// Nhận lệnh (say, play, sleep, status...) từ các chương trình khác trên máy
+// qua cổng localhost trong instance.json, có token
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlConfig {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // off, error, warn, info, debug hoặc trace
+    pub level: String,
+    // Mức riêng theo target của log, ví dụ "pycatai_pet_rust::speech" = "debug"
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 16,
+            overlay: true,
+            start_x: 20,
+            sprite_dir: PathBuf::from("sprites"),
+        }
+    }
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            app_switch_cooldown_secs: 300.0,
+            title_change_cooldown_secs: 600.0,
+            long_focus_secs: 2700.0,
+            long_focus_cooldown_secs: 2700.0,
+            idle_secs: 300.0,
+            idle_cooldown_secs: 1800.0,
+            return_cooldown_secs: 600.0,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            step_interval_secs: 16.0,
+            step_pixels: 5,
+            jump_chance: 0.2,
+            disappear_chance: 0.3,
+        }
+    }
+}
+
+impl Default for CommentConfig {
+    fn default() -> Self {
+        let schedule = CommentSchedule::default();
+        Self {
+            average_interval_secs: schedule.average.as_secs_f64(),
+            min_interval_secs: schedule.min.as_secs_f64(),
+            max_interval_secs: schedule.max.as_secs_f64(),
+            initial_comment_chance: 0.8,
+            http_timeout_secs: 15.0,
+            provider: Provider::Gemini,
+            api_key: None,
+            api_base_url: None,
+            screen_source: ScreenSourceKind::Screen,
+            screen_path: None,
+            persona: comment_generator::DEFAULT_PERSONA.to_string(),
+            tts: true,
+        }
+    }
+}
+
+impl Default for SpeechConfig {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            pitch: 1.0,
+            volume: 1.0,
+            voice: None,
+            queue: QueuePolicy::Interrupt,
+            backend: SpeechBackendKind::System,
+            piper_executable: PathBuf::from("piper"),
+            piper_model: None,
+            piper_speaker: None,
+        }
+    }
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self { enabled: true, volume: 0.8 }
+    }
+}
+
+impl Default for QuietConfig {
+    fn default() -> Self {
+        Self {
+            hours: Vec::new(),
+            fullscreen: true,
+            snooze_minutes: 30.0,
+        }
+    }
+}
+
+impl DisplayConfig {
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(self.tick_ms)
+    }
+}
+
+impl MovementConfig {
+    pub fn step_interval(&self) -> Duration {
+        Duration::from_secs_f64(self.step_interval_secs)
+    }
+}
+
+impl CommentConfig {
+    pub fn schedule(&self) -> CommentSchedule {
+        CommentSchedule {
+            average: Duration::from_secs_f64(self.average_interval_secs),
+            min: Duration::from_secs_f64(self.min_interval_secs),
+            max: Duration::from_secs_f64(self.max_interval_secs),
+        }
+    }
+
+    pub fn http_timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.http_timeout_secs)
+    }
+}
+
+impl QuietConfig {
+    pub fn is_quiet(&self, time: chrono::NaiveTime) -> bool {
+        self.hours.iter().any(|range| range.contains(time))
+    }
+
+    pub fn snooze(&self) -> Duration {
+        Duration::from_secs_f64(self.snooze_minutes * 60.0)
+    }
+}
+
+impl EventConfig {
+    pub fn cooldown(&self, kind: EventKind) -> Duration {
+        Duration::from_secs_f64(match kind {
+            EventKind::AppSwitch => self.app_switch_cooldown_secs,
+            EventKind::TitleChange => self.title_change_cooldown_secs,
+            EventKind::LongFocus => self.long_focus_cooldown_secs,
+            EventKind::Idle => self.idle_cooldown_secs,
+            EventKind::Return => self.return_cooldown_secs,
+        })
+    }
+
+    pub fn long_focus(&self) -> Duration {
+        Duration::from_secs_f64(self.long_focus_secs)
+    }
+
+    pub fn idle(&self) -> Duration {
+        Duration::from_secs_f64(self.idle_secs)
+    }
+}
+
+impl PrivacyConfig {
+    pub fn denies(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        self.deny_titles
+            .iter()
+            .any(|denied| !denied.trim().is_empty() && title.contains(&denied.trim().to_lowercase()))
+    }
+}
+
+impl Config {
+    // <config dir>/pycatai-pet/config.toml, e.g. %APPDATA% or ~/.config
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Config = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    // Load the user's config, writing the defaults on first run so there is
+    // a file to edit. Any problem falls back to the defaults.
+    pub fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            let config = Self::default();
+            if let Err(e) = config.save(path) {
+                error!("Failed to write default config: {}", e);
+            } else {
+                info!("Wrote default config to {}", path.display());
+            }
+            return config;
+        }
+
+        match Self::load(path) {
+            Ok(config) => {
+                info!("Loaded config from {}", path.display());
+                config
+            }
+            Err(e) => {
+                error!("{:#}, using defaults", e);
+                Self::default()
+            }
+        }
+    }
+
+    // Giữ chú thích và thứ tự trong file đang có, chỉ thay các giá trị.
+    // Ghi ra file tạm rồi đổi tên, để file không bao giờ bị ghi dở.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let new: toml_edit::Document = toml::to_string_pretty(self)?.parse()?;
+        let document = match std::fs::read_to_string(path).ok().and_then(|text| text.parse::<toml_edit::Document>().ok()) {
+            Some(mut existing) => {
+                merge_table(existing.as_table_mut(), new.as_table());
+                existing
+            }
+            None => new,
+        };
+        let temp = path.with_extension("toml.tmp");
+        std::fs::write(&temp, document.to_string())?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    // Checks every field and reports all problems at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if !(1..=1000).contains(&self.display.tick_ms) {
+            problems.push(format!("display.tick_ms must be between 1 and 1000 (got {})", self.display.tick_ms));
+        }
+
+        let movement = &self.movement;
+        if !(movement.step_interval_secs > 0.0 && movement.step_interval_secs <= 3600.0) {
+            problems.push(format!(
+                "movement.step_interval_secs must be greater than 0 and at most 3600 (got {})",
+                movement.step_interval_secs
+            ));
+        }
+        if !(1..=100).contains(&movement.step_pixels) {
+            problems.push(format!("movement.step_pixels must be between 1 and 100 (got {})", movement.step_pixels));
+        }
+        check_chance(&mut problems, "movement.jump_chance", movement.jump_chance);
+        check_chance(&mut problems, "movement.disappear_chance", movement.disappear_chance);
+
+        let comments = &self.comments;
+        for (name, value) in [
+            ("comments.average_interval_secs", comments.average_interval_secs),
+            ("comments.min_interval_secs", comments.min_interval_secs),
+            ("comments.max_interval_secs", comments.max_interval_secs),
+        ] {
+            if !(0.0..=86400.0).contains(&value) {
+                problems.push(format!("{} must be between 0 and 86400 (got {})", name, value));
+            }
+        }
+        if comments.min_interval_secs > comments.max_interval_secs {
+            problems.push(format!(
+                "comments.min_interval_secs ({}) must not exceed comments.max_interval_secs ({})",
+                comments.min_interval_secs, comments.max_interval_secs
+            ));
+        }
+        if comments.average_interval_secs < comments.min_interval_secs
+            || comments.average_interval_secs > comments.max_interval_secs
+        {
+            problems.push(format!(
+                "comments.average_interval_secs ({}) must be between min_interval_secs and max_interval_secs",
+                comments.average_interval_secs
+            ));
+        }
+        check_chance(&mut problems, "comments.initial_comment_chance", comments.initial_comment_chance);
+        if !(comments.http_timeout_secs > 0.0 && comments.http_timeout_secs <= MAX_HTTP_TIMEOUT_SECS) {
+            problems.push(format!(
+                "comments.http_timeout_secs must be greater than 0 and at most {} (got {})",
+                MAX_HTTP_TIMEOUT_SECS, comments.http_timeout_secs
+            ));
+        }
+
+        if let Some(url) = &comments.api_base_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push(format!("comments.api_base_url must start with http:// or https:// (got '{}')", url));
+            }
+        }
+
+        if let Err(e) = comment_generator::persona_prompt(&comments.persona) {
+            problems.push(format!("comments.persona: {}", e));
+        }
+        if let Err(e) = screen_source::from_config(comments) {
+            problems.push(format!("comments.screen_source: {}", e));
+        }
+
+        let speech = &self.speech;
+        for (name, value, (low, high)) in [
+            ("speech.rate", speech.rate, speech::RATE_RANGE),
+            ("speech.pitch", speech.pitch, speech::PITCH_RANGE),
+            ("speech.volume", speech.volume, (0.0, 1.0)),
+        ] {
+            if !(low..=high).contains(&value) {
+                problems.push(format!("{} must be between {} and {} (got {})", name, low, high, value));
+            }
+        }
+        if speech.backend == SpeechBackendKind::Piper {
+            if !cfg!(feature = "piper") {
+                problems.push("speech.backend = \"piper\" needs a build with --features piper".to_string());
+            }
+            match &speech.piper_model {
+                None => problems.push("speech.backend = \"piper\" needs speech.piper_model".to_string()),
+                Some(model) if !model.is_file() => {
+                    problems.push(format!("speech.piper_model {} does not exist", model.display()))
+                }
+                Some(_) => {}
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.sounds.volume) {
+            problems.push(format!("sounds.volume must be between 0 and 1 (got {})", self.sounds.volume));
+        }
+        if !(self.quiet.snooze_minutes > 0.0 && self.quiet.snooze_minutes <= 1440.0) {
+            problems.push(format!(
+                "quiet.snooze_minutes must be greater than 0 and at most 1440 (got {})",
+                self.quiet.snooze_minutes
+            ));
+        }
+
+        let events = &self.events;
+        for (name, value) in [
+            ("events.app_switch_cooldown_secs", events.app_switch_cooldown_secs),
+            ("events.title_change_cooldown_secs", events.title_change_cooldown_secs),
+            ("events.long_focus_cooldown_secs", events.long_focus_cooldown_secs),
+            ("events.idle_cooldown_secs", events.idle_cooldown_secs),
+            ("events.return_cooldown_secs", events.return_cooldown_secs),
+        ] {
+            if !(0.0..=86400.0).contains(&value) {
+                problems.push(format!("{} must be between 0 and 86400 (got {})", name, value));
+            }
+        }
+        for (name, value) in [("events.long_focus_secs", events.long_focus_secs), ("events.idle_secs", events.idle_secs)] {
+            if !(value > 0.0 && value <= 86400.0) {
+                problems.push(format!("{} must be greater than 0 and at most 86400 (got {})", name, value));
+            }
+        }
+
+        let logging = &self.logging;
+        let levels = std::iter::once(("logging.level".to_string(), &logging.level))
+            .chain(logging.modules.iter().map(|(module, level)| (format!("logging.modules.\"{}\"", module), level)));
+        for (name, level) in levels {
+            if level.parse::<log::LevelFilter>().is_err() {
+                problems.push(format!("{} must be one of off, error, warn, info, debug, trace (got '{}')", name, level));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("{}", problems.join("\n")))
+        }
+    }
+}
+
+// Đưa giá trị của `new` vào `existing`, giữ nguyên chú thích quanh các khoá
+// đã có; khoá không còn trong `new` (ví dụ api_key bị xoá) bị bỏ
+fn merge_table(existing: &mut toml_edit::Table, new: &toml_edit::Table) {
+    let stale: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in stale {
+        existing.remove(&key);
+    }
+
+    for (key, item) in new.iter() {
+        match (existing.get_mut(key), item) {
+            (Some(toml_edit::Item::Table(old)), toml_edit::Item::Table(table)) => merge_table(old, table),
+            (Some(toml_edit::Item::Value(old)), toml_edit::Item::Value(value)) => {
+                let decor = old.decor().clone();
+                *old = value.clone();
+                *old.decor_mut() = decor;
+            }
+            _ => {
+                existing.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+fn check_chance(problems: &mut Vec<String>, name: &str, value: f64) {
+    if !(0.0..=1.0).contains(&value) {
+        problems.push(format!("{} must be between 0 and 1 (got {})", name, value));
+    }
+}
+
+// Theo dõi thời điểm sửa file để nạp lại cấu hình khi người dùng lưu file
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self { path, modified }
+    }
+
+    // Returns the new config when the file changed since the last poll.
+    // Invalid edits are reported and the running config is kept.
+    pub fn poll(&mut self) -> Option<Config> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        match Config::load(&self.path) {
+            Ok(config) => {
+                info!("Reloaded config from {}", self.path.display());
+                Some(config)
+            }
+            Err(e) => {
+                error!("{:#}, keeping the current config", e);
+                None
+            }
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_roundtrips() {
+        let config = Config::default();
+        config.validate().unwrap();
+
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(Config::parse(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let config = Config::parse("[movement]\njump_chance = 0.5\n").unwrap();
+        assert_eq!(config.movement.jump_chance, 0.5);
+        assert_eq!(config.movement.step_pixels, MovementConfig::default().step_pixels);
+        assert_eq!(config.comments, CommentConfig::default());
+    }
+
+    #[test]
+    fn test_invalid_config_reports_every_problem() {
+        let error = Config::parse(
+            "[movement]\njump_chance = 1.5\n\n[comments]\nmin_interval_secs = 600.0\nmax_interval_secs = 60.0\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("movement.jump_chance must be between 0 and 1 (got 1.5)"));
+        assert!(error.contains("must not exceed comments.max_interval_secs"));
+
+        let error = Config::parse("[comments]\napi_base_url = \"localhost:8080\"\n").unwrap_err().to_string();
+        assert!(error.contains("comments.api_base_url must start with http://"));
+
+        let error = Config::parse("[sounds]\nvolume = 2.0\n\n[quiet]\nhours = [\"22:00-07:00\"]\n").unwrap_err().to_string();
+        assert!(error.contains("sounds.volume must be between 0 and 1 (got 2)"));
+        assert!(Config::parse("[quiet]\nhours = [\"late\"]\n").unwrap_err().to_string().contains("invalid time range"));
+
+        let privacy = Config::parse("[privacy]\ndeny_titles = [\"1password\", \" \"]\n").unwrap().privacy;
+        assert!(privacy.denies("Vault - 1Password"));
+        assert!(!privacy.denies("Terminal"));
+
+        let error = Config::parse("[logging]\nlevel = \"loud\"\nmodules = { reqwest = \"warn\" }\n").unwrap_err().to_string();
+        assert!(error.contains("logging.level must be one of"));
+        assert!(!error.contains("reqwest"));
+
+        let error = Config::parse("[events]\nidle_secs = 0\nreturn_cooldown_secs = -1\n").unwrap_err().to_string();
+        assert!(error.contains("events.idle_secs must be greater than 0"));
+        assert!(error.contains("events.return_cooldown_secs must be between 0 and 86400 (got -1)"));
+
+        // Lỗi chính tả trong tên trường cũng bị báo
+        assert!(Config::parse("[display]\ntick_msec = 16\n").is_err());
+    }
+
+    #[test]
+    fn test_watcher_reloads_changed_file() {
+        let path = std::env::temp_dir().join(format!("pycatai-config-{}.toml", std::process::id()));
+        Config::default().save(&path).unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(watcher.poll().is_none());
+
+        let mut config = Config::default();
+        config.movement.step_pixels = 7;
+        config.save(&path).unwrap();
+        // Make sure the change is visible even on coarse mtime filesystems
+        watcher.modified = None;
+        assert_eq!(watcher.poll().map(|c| c.movement.step_pixels), Some(7));
+
+        // File lỗi: giữ cấu hình cũ
+        std::fs::write(&path, "[movement]\nstep_pixels = 0\n").unwrap();
+        watcher.modified = None;
+        assert!(watcher.poll().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
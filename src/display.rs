@@ -1,39 +1,137 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use iced::{
-    window, Application, Command, Element, Settings, Subscription,
-    executor, Theme, Font,
+    keyboard, mouse, subscription, window, Application, Command, Element, Event, Settings,
+    Subscription, executor, Theme, Font,
 };
 use iced::widget::Container;
-use log::info;
+use clap::Parser;
+use log::{error, info};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::sprite_handler::SpriteController;
+use crate::cli::{Cli, ControlCommand, Overrides};
+use crate::comment_generator;
+use crate::config::{Config, ConfigWatcher};
+use crate::instance::{Incoming, Instance, Reply};
+use crate::logging;
+use crate::metrics;
+use crate::speech_bubble::{BubbleLayout, MAX_BUBBLE_HEIGHT, MAX_BUBBLE_WIDTH, TAIL_SIZE};
+use crate::sprite_handler::{SpriteController, CAT_SIZE};
+use crate::state::{self, PetState};
+use crate::timing::SystemClock;
+use crate::tray::{self, TrayAction, TrayBackend, TrayMenu};
+use crate::window_handler::{self, WindowInfo};
+
+// Kích thước cửa sổ trong suốt chứa mèo và khung chat: đủ chỗ cho khung chat
+// lớn nhất ở phía trên hoặc bên cạnh mèo
+pub const WINDOW_SIZE: (u32, u32) = (
+    (CAT_SIZE + TAIL_SIZE + MAX_BUBBLE_WIDTH) as u32,
+    (CAT_SIZE + TAIL_SIZE + MAX_BUBBLE_HEIGHT) as u32,
+);
+
+// Kiểm tra file cấu hình mỗi giây
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Lần chạy sau, menu khay hệ thống và Ctrl+C cần được trả lời nhanh hơn
+const INSTANCE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Thông tin khởi động truyền từ main
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    pub config: Config,
+    pub config_path: Option<PathBuf>,
+    pub overrides: Overrides,
+    pub seed: Option<u64>,
+    pub state_path: Option<PathBuf>,
+    pub state: Option<PetState>,
+    pub instance: Option<Arc<Instance>>,
+    pub shutdown: Arc<AtomicBool>,
+    pub tray: Option<Arc<dyn TrayBackend>>,
+    // Các bộ sprite chọn được từ khay hệ thống
+    pub skins: Vec<PathBuf>,
+}
 
 pub struct Display {
+    flags: Flags,
+    config_watcher: Option<ConfigWatcher>,
     sprite_controller: SpriteController,
+    window_position: (i32, i32),
+    
+    // Overlay: luôn nằm trên cùng, chỉ phần mèo không trong suốt nhận chuột
+    overlay: bool,
+    window_info: Arc<dyn WindowInfo>,
+    window_id: Option<u64>,
+    hit_key: Option<(String, usize, BubbleLayout)>,
+    
+    // Menu khay hệ thống đã hiển thị lần cuối
+    tray_menu: TrayMenu,
+    // Persona và sprite chọn từ khay, bỏ khi config.toml được sửa
+    picks: Overrides,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Tick,
+    WindowId(u64),
+    ToggleOverlay,
+    CheckConfig,
+    Pet,
+    Feed,
+    ToggleSnooze,
+    ToggleDebug,
+    SaveState,
+    CheckInstance,
+    CheckTray,
+    CloseRequested,
+    // Trả lời của AI cho bình luận đang chờ, None khi lỗi
+    CommentReady(Option<String>),
+    // Lệnh điều khiển, từ API hoặc menu khay hệ thống
+    Say(String),
+    Play(String),
+    Sleep,
+    Wake,
+    CommentNow,
+    SetPersona(String),
+    CommandFinished { command: String, exit_code: Option<i32> },
 }
 
 impl Application for Display {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(flags: Flags) -> (Self, Command<Message>) {
         info!("Initializing Display");
         
         // Create main window and controller
-        let sprite_controller = SpriteController::new();
+        let window_info = window_handler::default_backend();
+        let mut sprite_controller =
+            SpriteController::new(window_info.clone(), Arc::new(SystemClock), &flags.config, flags.seed);
+        if let Some(state) = &flags.state {
+            if let Err(e) = sprite_controller.restore(state) {
+                error!("Failed to restore cat state: {}", e);
+            }
+        }
+        let window_position = sprite_controller.window_position();
         
         (
             Self {
+                config_watcher: flags.config_path.clone().map(ConfigWatcher::new),
+                overlay: flags.config.display.overlay,
+                flags,
                 sprite_controller,
+                window_position,
+                window_info,
+                window_id: None,
+                hit_key: None,
+                tray_menu: TrayMenu::default(),
+                picks: Overrides::default(),
             },
-            Command::none(),
+            // The native window id is needed to shape the input region
+            window::fetch_id(Message::WindowId),
         )
     }
 
@@ -45,9 +143,133 @@ impl Application for Display {
         match message {
             Message::Tick => {
                 // Update state on each tick
+                let started = Instant::now();
                 if let Err(e) = self.sprite_controller.handle_animation() {
                     log::error!("Animation error: {}", e);
                 }
+                metrics::observe(metrics::FRAME_MS, started.elapsed().as_secs_f64() * 1000.0);
+                
+                self.update_input_region();
+                
+                let comment = match self.sprite_controller.take_comment_job() {
+                    Some(job) => Command::perform(job, Message::CommentReady),
+                    None => Command::none(),
+                };
+                
+                // Move the window along with the cat
+                let position = self.sprite_controller.window_position();
+                if position != self.window_position {
+                    self.window_position = position;
+                    return Command::batch([comment, window::move_to(position.0, position.1)]);
+                }
+                comment
+            }
+            Message::CommentReady(response) => {
+                self.sprite_controller.comment_ready(response);
+                Command::none()
+            }
+            Message::WindowId(id) => {
+                info!("Native window id: {}", id);
+                self.window_id = Some(id);
+                self.hit_key = None;
+                self.update_input_region();
+                Command::none()
+            }
+            Message::ToggleOverlay => {
+                self.overlay = !self.overlay;
+                info!("Overlay mode {}", if self.overlay { "on" } else { "off" });
+                self.hit_key = None;
+                self.update_input_region();
+                window::change_level(self.window_level())
+            }
+            Message::CheckConfig => {
+                let Some(mut config) = self.config_watcher.as_mut().and_then(ConfigWatcher::poll) else {
+                    return Command::none();
+                };
+                
+                // Command-line flags still win over the edited file, menu
+                // picks made before the edit do not
+                self.flags.overrides.apply(&mut config);
+                self.picks = Overrides::default();
+                self.sprite_controller.apply_config(&config);
+                logging::apply_config(&config.logging);
+                let overlay_changed = config.display.overlay != self.flags.config.display.overlay;
+                self.flags.config = config;
+                
+                if overlay_changed {
+                    self.overlay = self.flags.config.display.overlay;
+                    self.hit_key = None;
+                    self.update_input_region();
+                    return window::change_level(self.window_level());
+                }
+                Command::none()
+            }
+            Message::Pet => {
+                self.sprite_controller.pet();
+                Command::none()
+            }
+            Message::Feed => {
+                self.sprite_controller.feed();
+                Command::none()
+            }
+            Message::ToggleSnooze => {
+                self.sprite_controller.toggle_snooze();
+                Command::none()
+            }
+            Message::ToggleDebug => {
+                self.sprite_controller.toggle_debug();
+                Command::none()
+            }
+            Message::SaveState => {
+                self.save_state();
+                Command::none()
+            }
+            Message::CheckInstance => {
+                if self.flags.shutdown.load(Ordering::SeqCst) {
+                    return self.shutdown();
+                }
+                match self.flags.instance.as_ref().and_then(|instance| instance.poll()) {
+                    Some(Incoming::Launch(args)) => self.apply_forwarded(args),
+                    Some(Incoming::Control(command, reply)) => {
+                        let answer = match self.handle_control(command) {
+                            Ok(status) => Reply::ok(status),
+                            Err(e) => Reply::error(format!("{:#}", e)),
+                        };
+                        // The request may have timed out already
+                        let _ = reply.send(answer);
+                    }
+                    None => {}
+                }
+                Command::none()
+            }
+            Message::CheckTray => {
+                let Some(tray) = self.flags.tray.clone() else {
+                    return Command::none();
+                };
+                let command = match tray.poll() {
+                    Some(action) => self.handle_tray(action),
+                    None => Command::none(),
+                };
+                
+                // Tạm dừng hết hạn hoặc config.toml đổi cũng phải hiện lên menu
+                let menu = self.tray_menu();
+                if menu != self.tray_menu {
+                    tray.update(&menu);
+                    self.tray_menu = menu;
+                }
+                command
+            }
+            Message::CloseRequested => self.shutdown(),
+            message @ (Message::Say(_)
+            | Message::Play(_)
+            | Message::Sleep
+            | Message::Wake
+            | Message::CommentNow
+            | Message::SetPersona(_)
+            | Message::CommandFinished { .. }) => {
+                if let Err(e) = self.control(message) {
+                    info!("{}", e);
+                }
                 Command::none()
             }
         }
@@ -55,11 +277,51 @@ impl Application for Display {
 
     fn subscription(&self) -> Subscription<Message> {
         // Register subscription to receive time-based events
-        iced::time::every(std::time::Duration::from_millis(16))
-            .map(|_| Message::Tick)
+        let ticks = iced::time::every(self.flags.config.display.tick())
+            .map(|_| Message::Tick);
+        
+        // Nạp lại cấu hình khi file thay đổi
+        let config = iced::time::every(CONFIG_POLL_INTERVAL)
+            .map(|_| Message::CheckConfig);
+        
+        let save = iced::time::every(state::SAVE_INTERVAL)
+            .map(|_| Message::SaveState);
+        
+        let instance = iced::time::every(INSTANCE_POLL_INTERVAL)
+            .map(|_| Message::CheckInstance);
+        
+        let tray = iced::time::every(INSTANCE_POLL_INTERVAL)
+            .map(|_| Message::CheckTray);
+        
+        // Khi cửa sổ mèo đang được focus: phím O bật/tắt chế độ overlay,
+        // phím S tạm tắt bình luận, phím F cho mèo ăn, phím D bật/tắt bảng
+        // debug; bấm chuột vào mèo là vuốt ve
+        let keys = subscription::events_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::O,
+                ..
+            }) => Some(Message::ToggleOverlay),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::S,
+                ..
+            }) => Some(Message::ToggleSnooze),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::F,
+                ..
+            }) => Some(Message::Feed),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::D,
+                ..
+            }) => Some(Message::ToggleDebug),
+            Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested),
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => Some(Message::Pet),
+            _ => None,
+        });
+        
+        Subscription::batch([ticks, config, save, instance, tray, keys])
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         // Display UI
         Container::new(self.sprite_controller.view())
             .width(iced::Length::Fill)
@@ -73,16 +335,203 @@ impl Application for Display {
 }
 
 impl Display {
-    pub fn new() -> Self {
-        let (app, _) = <Self as Application>::new(());
+    fn window_level(&self) -> window::Level {
+        if self.overlay {
+            window::Level::AlwaysOnTop
+        } else {
+            window::Level::Normal
+        }
+    }
+    
+    fn save_state(&self) {
+        let Some(path) = &self.flags.state_path else {
+            return;
+        };
+        if let Err(e) = self.sprite_controller.state().save(path) {
+            error!("Failed to save cat state: {}", e);
+        }
+    }
+    
+    // Thoát gọn gàng: dừng TTS và bình luận, lưu trạng thái, ghi hết log
+    fn shutdown(&mut self) -> Command<Message> {
+        info!("Shutting down");
+        self.sprite_controller.shutdown();
+        self.save_state();
+        for line in metrics::snapshot().summary() {
+            info!("Metrics: {}", line);
+        }
+        log::logger().flush();
+        window::close()
+    }
+    
+    // Tham số dòng lệnh của lần chạy sau, áp dụng như khi config.toml đổi
+    fn apply_forwarded(&mut self, args: Vec<String>) {
+        match Cli::try_parse_from(std::iter::once("pycatai-pet".to_string()).chain(args)) {
+            Ok(cli) => {
+                let overrides = cli.overrides();
+                if self.apply_overrides(&overrides) {
+                    self.flags.overrides.merge(overrides);
+                }
+                self.sprite_controller.greet();
+            }
+            Err(e) => error!("Ignoring forwarded options: {}", e),
+        }
+    }
+    
+    fn apply_overrides(&mut self, overrides: &Overrides) -> bool {
+        let mut config = self.flags.config.clone();
+        overrides.apply(&mut config);
+        if let Err(e) = config.validate() {
+            error!("Ignoring options:\n{}", e);
+            return false;
+        }
+        
+        self.sprite_controller.apply_config(&config);
+        self.flags.config = config;
+        true
+    }
+    
+    fn pick(&mut self, overrides: Overrides) {
+        if self.apply_overrides(&overrides) {
+            self.picks.merge(overrides);
+        }
+    }
+    
+    fn handle_tray(&mut self, action: TrayAction) -> Command<Message> {
+        info!("Tray menu: {:?}", action);
+        match action {
+            TrayAction::TogglePause => self.sprite_controller.toggle_snooze(),
+            TrayAction::ToggleOverlay => return self.update(Message::ToggleOverlay),
+            TrayAction::CommentNow => return self.update(Message::CommentNow),
+            TrayAction::ToggleMute => {
+                let muted = !self.sprite_controller.is_muted();
+                self.sprite_controller.set_muted(muted);
+            }
+            TrayAction::Persona(persona) => self.pick(Overrides::persona(persona)),
+            TrayAction::Skin(dir) => self.pick(Overrides::sprites(dir)),
+            TrayAction::Settings => self.open_settings(),
+            TrayAction::Quit => return self.shutdown(),
+        }
+        Command::none()
+    }
+    
+    // Lệnh từ chương trình khác; trạng thái mèo với `status`
+    fn handle_control(&mut self, command: ControlCommand) -> Result<Option<serde_json::Value>> {
+        if !self.flags.config.control.enabled {
+            return Err(anyhow!("The control API is off, set enabled = true under [control] in config.toml"));
+        }
+        let message = match command {
+            ControlCommand::Status => return self.status().map(Some),
+            ControlCommand::Say { text } => Message::Say(text),
+            ControlCommand::Play { animation } => Message::Play(animation),
+            ControlCommand::Sleep => Message::Sleep,
+            ControlCommand::Wake => Message::Wake,
+            ControlCommand::CommentNow => Message::CommentNow,
+            ControlCommand::SetPersona { persona } => Message::SetPersona(persona),
+            ControlCommand::Finished { command, exit_code } => Message::CommandFinished { command, exit_code },
+        };
+        self.control(message).map(|_| None)
+    }
+    
+    fn control(&mut self, message: Message) -> Result<()> {
+        match message {
+            Message::Say(text) => self.sprite_controller.say(&text),
+            Message::Play(animation) => self.sprite_controller.play(&animation),
+            Message::Sleep => {
+                self.sprite_controller.sleep();
+                Ok(())
+            }
+            Message::Wake => self.sprite_controller.wake(),
+            Message::CommentNow => self.sprite_controller.comment_now(),
+            Message::SetPersona(persona) => {
+                comment_generator::persona_prompt(&persona)?;
+                self.pick(Overrides::persona(persona));
+                Ok(())
+            }
+            Message::CommandFinished { command, exit_code } => self.sprite_controller.react_to_command(&command, exit_code),
+            other => Err(anyhow!("{:?} is not a control command", other)),
+        }
+    }
+    
+    fn status(&self) -> Result<serde_json::Value> {
+        let controller = &self.sprite_controller;
+        let mut status = serde_json::to_value(controller.snapshot())?;
+        let state = controller.state();
+        status["needs"] = serde_json::to_value(&state.needs)?;
+        status["persona"] = state.persona.into();
+        status["skin"] = tray::skin_name(&state.skin).into();
+        status["paused"] = controller.is_snoozed().into();
+        status["muted"] = controller.is_muted().into();
+        status["version"] = env!("CARGO_PKG_VERSION").into();
+        Ok(status)
+    }
+    
+    fn tray_menu(&self) -> TrayMenu {
+        let persona = self.flags.config.comments.persona.clone();
+        let mut personas: Vec<String> = comment_generator::persona_names().into_iter().map(String::from).collect();
+        if !personas.contains(&persona) {
+            personas.push(persona.clone());
+        }
+        let skin = self.flags.config.display.sprite_dir.clone();
+        let mut skins = self.flags.skins.clone();
+        if !skins.contains(&skin) {
+            skins.insert(0, skin.clone());
+        }
+        
+        TrayMenu {
+            paused: self.sprite_controller.is_snoozed(),
+            overlay: self.overlay,
+            muted: self.sprite_controller.is_muted(),
+            persona,
+            personas,
+            skin,
+            skins,
+        }
+    }
+    
+    // Cửa sổ cài đặt chạy trong tiến trình riêng; config.toml tự nạp lại
+    // khi nó lưu
+    fn open_settings(&self) {
+        let Some(path) = &self.flags.config_path else {
+            error!("No config file to edit");
+            return;
+        };
+        let spawned = std::env::current_exe().and_then(|exe| {
+            std::process::Command::new(exe).arg("--config").arg(path).arg("settings").spawn()
+        });
+        if let Err(e) = spawned {
+            error!("Failed to open the settings window: {}", e);
+        }
+    }
+    
+    // Reshape the clickable area when the visible sprite pixels change
+    fn update_input_region(&mut self) {
+        let Some(window_id) = self.window_id else {
+            return;
+        };
+        
+        let key = self.overlay.then(|| self.sprite_controller.frame_key());
+        if self.hit_key.is_some() && self.hit_key == key {
+            return;
+        }
+        
+        let region = key.as_ref().map(|_| self.sprite_controller.hit_region());
+        if let Err(e) = self.window_info.set_input_region(window_id, region.as_deref()) {
+            error!("Failed to update input region: {}", e);
+        }
+        self.hit_key = key;
+    }
+    
+    pub fn new(flags: Flags) -> Self {
+        let (app, _) = <Self as Application>::new(flags);
         app
     }
     
     pub fn run(self) -> Result<()> {
         let settings = Settings {
             window: window::Settings {
-                size: (200, 200),
-                position: window::Position::Specific(100, 500),
+                size: WINDOW_SIZE,
+                position: window::Position::Specific(self.window_position.0, self.window_position.1),
                 min_size: None,
                 max_size: None,
                 visible: true,
@@ -90,14 +539,15 @@ impl Display {
                 decorations: false,
                 transparent: true,
                 icon: None,
-                level: window::Level::Normal,
+                level: self.window_level(),
                 platform_specific: window::PlatformSpecific::default(),
-                ..Default::default()
             },
+            flags: self.flags.clone(),
             default_font: Font::default(),
             default_text_size: 16.0,
             antialiasing: true,
-            exit_on_close_request: true,
+            // Thoát gọn gàng trước khi đóng, xem Display::shutdown
+            exit_on_close_request: false,
             ..Default::default()
         };
         
@@ -0,0 +1,228 @@
+// Sự kiện trên màn hình làm mèo bình luận ngay thay vì chờ lịch ngẫu nhiên:
+// đổi ứng dụng, mở tài liệu hay tab mới, ngồi lâu ở một ứng dụng, rời máy và
+// quay lại. Mỗi loại có thời gian chờ riêng để mèo không nói liên tục.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::EventConfig;
+use crate::window_handler::ForegroundWindow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    AppSwitch,
+    TitleChange,
+    LongFocus,
+    Idle,
+    Return,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityEvent {
+    AppSwitch { from: String, to: String },
+    TitleChange { app: String, title: String },
+    LongFocus { app: String, focused_for: Duration },
+    Idle { idle_for: Duration },
+    Return { away_for: Duration },
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AppSwitch => "app_switch",
+            Self::TitleChange => "title_change",
+            Self::LongFocus => "long_focus",
+            Self::Idle => "idle",
+            Self::Return => "return",
+        }
+    }
+}
+
+impl ActivityEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::AppSwitch { .. } => EventKind::AppSwitch,
+            Self::TitleChange { .. } => EventKind::TitleChange,
+            Self::LongFocus { .. } => EventKind::LongFocus,
+            Self::Idle { .. } => EventKind::Idle,
+            Self::Return { .. } => EventKind::Return,
+        }
+    }
+
+    // Câu mô tả sự kiện, thêm vào prompt để AI biết vì sao mèo lên tiếng
+    pub fn context(&self) -> String {
+        match self {
+            Self::AppSwitch { from, to } => {
+                format!("The human just switched from {} to {}.", app_name(from), app_name(to))
+            }
+            Self::TitleChange { app, title } => format!("The human just opened \"{}\" in {}.", title, app_name(app)),
+            Self::LongFocus { app, focused_for } => format!(
+                "The human has been in {} for {} minutes straight.",
+                app_name(app),
+                focused_for.as_secs() / 60
+            ),
+            Self::Idle { idle_for } => format!(
+                "The human has not touched the keyboard or mouse for {} minutes.",
+                idle_for.as_secs() / 60
+            ),
+            Self::Return { away_for } => {
+                format!("The human just came back after {} minutes away.", away_for.as_secs() / 60)
+            }
+        }
+    }
+}
+
+fn app_name(app: &str) -> &str {
+    if app.is_empty() {
+        "an unnamed app"
+    } else {
+        app
+    }
+}
+
+pub struct ActivityWatcher {
+    config: EventConfig,
+    // Ứng dụng và tiêu đề đang focus lần trước
+    app: Option<String>,
+    title: String,
+    focused_since: Instant,
+    // Lúc người dùng bắt đầu rời máy, khi đang vắng mặt
+    away_since: Option<Instant>,
+    commented_at: HashMap<EventKind, Instant>,
+}
+
+impl ActivityWatcher {
+    pub fn new(config: &EventConfig, now: Instant) -> Self {
+        Self {
+            config: config.clone(),
+            app: None,
+            title: String::new(),
+            focused_since: now,
+            away_since: None,
+            commented_at: HashMap::new(),
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &EventConfig) {
+        self.config = config.clone();
+    }
+
+    // Gọi định kỳ với cửa sổ đang focus (None khi không biết, hoặc cửa sổ bị
+    // ẩn vì quyền riêng tư) và thời gian không có thao tác. Trả về sự kiện
+    // quan trọng nhất vừa xảy ra mà đã hết thời gian chờ.
+    pub fn observe(&mut self, window: Option<&ForegroundWindow>, idle: Option<Duration>, now: Instant) -> Option<ActivityEvent> {
+        let mut events = Vec::new();
+
+        if let Some(idle) = idle {
+            match self.away_since {
+                Some(since) if idle < self.config.idle() => {
+                    self.away_since = None;
+                    self.focused_since = now;
+                    events.push(ActivityEvent::Return {
+                        away_for: now.saturating_duration_since(since),
+                    });
+                }
+                None if idle >= self.config.idle() => {
+                    self.away_since = Some(now.checked_sub(idle).unwrap_or(now));
+                    events.push(ActivityEvent::Idle { idle_for: idle });
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(window) = window {
+            match &self.app {
+                Some(app) if *app != window.app => {
+                    events.push(ActivityEvent::AppSwitch {
+                        from: app.clone(),
+                        to: window.app.clone(),
+                    });
+                    self.focused_since = now;
+                }
+                Some(_) if window.title != self.title && !window.title.is_empty() => {
+                    events.push(ActivityEvent::TitleChange {
+                        app: window.app.clone(),
+                        title: window.title.clone(),
+                    });
+                }
+                Some(_) => {}
+                // Cửa sổ đầu tiên nhìn thấy không phải là một lần chuyển
+                None => self.focused_since = now,
+            }
+            self.app = Some(window.app.clone());
+            self.title = window.title.clone();
+
+            let focused_for = now.saturating_duration_since(self.focused_since);
+            if self.away_since.is_none() && focused_for >= self.config.long_focus() {
+                self.focused_since = now;
+                events.push(ActivityEvent::LongFocus {
+                    app: window.app.clone(),
+                    focused_for,
+                });
+            }
+        }
+
+        if !self.config.enabled {
+            return None;
+        }
+        events.into_iter().find(|event| self.is_ready(event.kind(), now))
+    }
+
+    fn is_ready(&self, kind: EventKind, now: Instant) -> bool {
+        self.commented_at
+            .get(&kind)
+            .is_none_or(|at| now.saturating_duration_since(*at) >= self.config.cooldown(kind))
+    }
+
+    // Mèo đã bình luận về sự kiện loại này, bắt đầu thời gian chờ
+    pub fn commented(&mut self, kind: EventKind, now: Instant) {
+        self.commented_at.insert(kind, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handler::WindowRect;
+
+    fn window(app: &str, title: &str) -> ForegroundWindow {
+        ForegroundWindow {
+            title: title.to_string(),
+            app: app.to_string(),
+            rect: WindowRect::new(0, 0, 800, 600),
+            fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn test_events_and_cooldowns() {
+        let start = Instant::now();
+        let config = EventConfig::default();
+        let mut watcher = ActivityWatcher::new(&config, start);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let active = Some(Duration::ZERO);
+
+        assert_eq!(watcher.observe(Some(&window("code", "main.rs")), active, at(0)), None);
+        let switch = watcher.observe(Some(&window("firefox", "Docs")), active, at(10)).unwrap();
+        assert_eq!(switch.context(), "The human just switched from code to firefox.");
+        watcher.commented(switch.kind(), at(10));
+
+        // Chuyển lại trong lúc chờ: không bình luận, nhưng vẫn ghi nhận tab mới
+        assert_eq!(watcher.observe(Some(&window("code", "main.rs")), active, at(20)), None);
+        let tab = watcher.observe(Some(&window("code", "lib.rs")), active, at(30)).unwrap();
+        assert_eq!(tab.kind(), EventKind::TitleChange);
+        assert!(tab.context().contains("\"lib.rs\" in code"));
+
+        let long = at(30) + config.long_focus();
+        let focus = watcher.observe(Some(&window("code", "lib.rs")), active, long).unwrap();
+        assert_eq!(focus.kind(), EventKind::LongFocus);
+
+        let idle = watcher.observe(None, Some(config.idle()), long + config.idle()).unwrap();
+        assert_eq!(idle.kind(), EventKind::Idle);
+        let back = watcher.observe(None, active, long + config.idle() * 3).unwrap();
+        assert_eq!(back, ActivityEvent::Return { away_for: config.idle() * 3 });
+
+        let mut disabled = ActivityWatcher::new(&EventConfig { enabled: false, ..config }, start);
+        disabled.observe(Some(&window("code", "main.rs")), active, at(0));
+        assert_eq!(disabled.observe(Some(&window("firefox", "Docs")), active, at(10)), None);
+    }
+}
@@ -0,0 +1,320 @@
+// Chỉ một con mèo cho mỗi file cấu hình: lần chạy đầu giữ khoá
+// instance.lock và nghe trên một cổng localhost; lần chạy sau chuyển tham số
+// dòng lệnh qua cổng đó rồi thoát. Cổng và token nằm trong instance.json.
+// Cùng cổng đó nhận lệnh điều khiển (`pycatai-pet control ...` hoặc script
+// gửi một dòng JSON) khi control.enabled = true.
+use anyhow::{anyhow, Context, Result};
+use fs2::FileExt;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cli::ControlCommand;
+
+const LOCK_FILE: &str = "instance.lock";
+const INFO_FILE: &str = "instance.json";
+
+// Lần chạy đầu có thể chưa kịp ghi instance.json
+const CONNECT_RETRIES: u32 = 20;
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+// Display trả lời ở lần kiểm tra tiếp theo (INSTANCE_POLL_INTERVAL); bình
+// luận chạy nền nên luồng giao diện không bị chặn lâu
+const REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+// Một dòng yêu cầu, đủ cho một câu dài để mèo nói
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Info {
+    port: u16,
+    token: String,
+}
+
+// Một dòng JSON, ví dụ {"token": "...", "command": {"say": {"text": "Build done"}}}
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    token: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<ControlCommand>,
+}
+
+// Trả lời một dòng JSON: {"ok": true}, kèm status hoặc lỗi
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reply {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<serde_json::Value>,
+}
+
+impl Reply {
+    pub fn ok(status: Option<serde_json::Value>) -> Self {
+        Self { ok: true, error: None, status }
+    }
+
+    pub fn error(error: impl ToString) -> Self {
+        Self { ok: false, error: Some(error.to_string()), status: None }
+    }
+}
+
+// Việc Display cần làm: tham số của một lần chạy sau, hoặc một lệnh điều
+// khiển đang chờ trả lời
+#[derive(Debug)]
+pub enum Incoming {
+    Launch(Vec<String>),
+    Control(ControlCommand, Sender<Reply>),
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    // Khoá được giữ tới khi chương trình thoát
+    _lock: File,
+    incoming: Mutex<Receiver<Incoming>>,
+}
+
+impl Instance {
+    // None: another cat is already running for this config and got `args`
+    pub fn acquire(config_path: &Path, args: &[String]) -> Result<Option<Self>> {
+        let lock_path = config_path.with_file_name(LOCK_FILE);
+        let info_path = config_path.with_file_name(INFO_FILE);
+        if let Some(dir) = lock_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        if let Err(e) = lock.try_lock_exclusive() {
+            if e.kind() != fs2::lock_contended_error().kind() {
+                return Err(anyhow!("Failed to lock {}: {}", lock_path.display(), e));
+            }
+            forward(&info_path, args)?;
+            return Ok(None);
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let info = Info {
+            port: listener.local_addr()?.port(),
+            token: format!("{:016x}{:016x}", rand::random::<u64>(), rand::random::<u64>()),
+        };
+        let temp = info_path.with_extension("json.tmp");
+        write_private(&temp, &serde_json::to_string(&info)?)?;
+        std::fs::rename(&temp, &info_path)?;
+        info!("Listening for other launches on port {}", info.port);
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || listen(listener, info.token, sender));
+        Ok(Some(Self {
+            _lock: lock,
+            incoming: Mutex::new(receiver),
+        }))
+    }
+
+    pub fn poll(&self) -> Option<Incoming> {
+        self.incoming.lock().ok()?.try_recv().ok()
+    }
+}
+
+// Gửi lệnh cho con mèo đang chạy với file cấu hình này
+pub fn send_command(config_path: &Path, command: ControlCommand) -> Result<Reply> {
+    let info_path = config_path.with_file_name(INFO_FILE);
+    let request = |token| Request { token, args: Vec::new(), command: Some(command) };
+    exchange(&info_path, request, REPLY_TIMEOUT + IO_TIMEOUT)
+        .with_context(|| format!("No pyCatAI-pet is answering for {}", config_path.display()))
+}
+
+fn forward(info_path: &Path, args: &[String]) -> Result<()> {
+    let mut last_error = anyhow!("no answer");
+    for _ in 0..CONNECT_RETRIES {
+        match try_forward(info_path, args) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+        std::thread::sleep(RETRY_DELAY);
+    }
+    Err(last_error.context("Another pyCatAI-pet is running but did not answer"))
+}
+
+fn try_forward(info_path: &Path, args: &[String]) -> Result<()> {
+    let request = |token| Request { token, args: args.to_vec(), command: None };
+    let reply = exchange(info_path, request, IO_TIMEOUT)?;
+    match reply.error {
+        None if reply.ok => Ok(()),
+        error => Err(anyhow!("Unexpected reply: {}", error.unwrap_or_default())),
+    }
+}
+
+fn exchange(info_path: &Path, request: impl FnOnce(String) -> Request, timeout: Duration) -> Result<Reply> {
+    let info: Info = serde_json::from_str(&std::fs::read_to_string(info_path)?)?;
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, info.port));
+    let mut stream = TcpStream::connect_timeout(&address, IO_TIMEOUT)?;
+    stream.set_read_timeout(Some(timeout))?;
+
+    writeln!(stream, "{}", serde_json::to_string(&request(info.token))?)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(serde_json::from_str(&reply)?)
+}
+
+// Mỗi kết nối một luồng: lệnh đang chờ Display không chặn lần chạy khác
+fn listen(listener: TcpListener, token: String, sender: Sender<Incoming>) {
+    let token = Arc::new(token);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let (token, sender) = (token.clone(), sender.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = serve(stream, &token, &sender) {
+                error!("Failed to handle a request from another process: {:#}", e);
+            }
+        });
+    }
+}
+
+fn serve(mut stream: TcpStream, token: &str, sender: &Sender<Incoming>) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_BYTES)).read_line(&mut line)?;
+    let request: Request = match serde_json::from_str(&line) {
+        Ok(request) => request,
+        Err(e) => return reply(&mut stream, Reply::error(format!("Invalid request: {}", e))),
+    };
+    if !tokens_match(&request.token, token) {
+        reply(&mut stream, Reply::error("denied"))?;
+        return Err(anyhow!("Wrong token"));
+    }
+
+    let Some(command) = request.command else {
+        info!("Another launch forwarded {:?}", request.args);
+        sender.send(Incoming::Launch(request.args)).map_err(|_| anyhow!("Display is gone"))?;
+        return reply(&mut stream, Reply::ok(None));
+    };
+    info!("Control command {:?}", command);
+    let (reply_sender, replies) = mpsc::channel();
+    sender.send(Incoming::Control(command, reply_sender)).map_err(|_| anyhow!("Display is gone"))?;
+    let answer = replies
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| Reply::error("The cat did not answer in time"));
+    reply(&mut stream, answer)
+}
+
+// Ai đọc được token thì điều khiển được con mèo: trên Unix chỉ chủ file đọc
+// được (0600). Trên Windows thư mục config của người dùng đã có quyền riêng.
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    let _ = std::fs::remove_file(path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+// So sánh hết mọi byte, để thời gian trả lời không lộ token đúng tới đâu
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn reply(stream: &mut TcpStream, reply: Reply) -> Result<()> {
+    writeln!(stream, "{}", serde_json::to_string(&reply)?)?;
+    Ok(())
+}
+
+// Ctrl+C hoặc SIGTERM đặt cờ; Display thấy cờ thì tắt gọn gàng
+pub fn shutdown_signal() -> Arc<AtomicBool> {
+    let requested = Arc::new(AtomicBool::new(false));
+    let flag = requested.clone();
+    if let Err(e) = ctrlc::set_handler(move || {
+        info!("Shutdown requested");
+        flag.store(true, Ordering::SeqCst);
+    }) {
+        error!("Failed to install the shutdown handler: {}", e);
+    }
+    requested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_second_launch_forwards_its_arguments() {
+        let dir = std::env::temp_dir().join(format!("pycatai-instance-{}", std::process::id()));
+        let config_path = dir.join("config.toml");
+        let first = Instance::acquire(&config_path, &[]).unwrap().expect("first launch runs");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join(INFO_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let args = vec!["--mute".to_string()];
+        assert!(Instance::acquire(&config_path, &args).unwrap().is_none());
+        match next(&first) {
+            Incoming::Launch(forwarded) => assert_eq!(forwarded, args),
+            other => panic!("expected the launch, got {:?}", other),
+        }
+
+        // Lệnh điều khiển chờ Display trả lời
+        let path = config_path.clone();
+        let client = std::thread::spawn(move || send_command(&path, ControlCommand::Status));
+        match next(&first) {
+            Incoming::Control(ControlCommand::Status, reply) => {
+                reply.send(Reply::ok(Some(serde_json::json!({ "state": "idle" })))).unwrap()
+            }
+            other => panic!("expected a status query, got {:?}", other),
+        }
+        let reply = client.join().unwrap().unwrap();
+        assert!(reply.ok);
+        assert_eq!(reply.status.unwrap()["state"], "idle");
+
+        // Sai token thì bị từ chối
+        let mut info: Info = serde_json::from_str(&std::fs::read_to_string(dir.join(INFO_FILE)).unwrap()).unwrap();
+        info.token = "guess".to_string();
+        std::fs::write(dir.join(INFO_FILE), serde_json::to_string(&info).unwrap()).unwrap();
+        assert!(try_forward(&dir.join(INFO_FILE), &args).is_err());
+        assert!(tokens_match("0123abcd", "0123abcd"));
+        assert!(!tokens_match("0123abce", "0123abcd"));
+        assert!(!tokens_match("0123", "0123abcd"));
+
+        drop(first);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn next(instance: &Instance) -> Incoming {
+        let deadline = Instant::now() + IO_TIMEOUT;
+        loop {
+            if let Some(incoming) = instance.poll() {
+                return incoming;
+            }
+            assert!(Instant::now() < deadline, "nothing arrived");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,324 @@
+// Ghi log dạng logfmt vào file xoay vòng trong thư mục dữ liệu của hệ điều
+// hành, đồng thời ra stderr. API key và các bí mật khác bị che trước khi ghi.
+// Khi chương trình panic, báo cáo lỗi kèm các dòng log gần nhất được ghi
+// cạnh file log.
+use anyhow::Result;
+use chrono::Local;
+use env_logger::filter::{Builder as FilterBuilder, Filter};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use crate::config::{LoggingConfig, APP_DIR};
+use crate::metrics;
+
+const LOG_FILE: &str = "pycatai-pet.log";
+// Xoay vòng khi file vượt quá 1 MiB, giữ thêm 4 file cũ (.1 tới .4)
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const KEPT_FILES: usize = 4;
+// Số dòng log gần nhất đưa vào báo cáo crash
+const RECENT_LINES: usize = 200;
+// Chuỗi ngắn hơn thế này không coi là bí mật, tránh che nhầm cả câu
+const MIN_SECRET_LEN: usize = 8;
+const REDACTED: &str = "[redacted]";
+
+struct FileLogger {
+    filter: RwLock<Filter>,
+    // Mức từ --log-level, thắng logging.level trong config.toml
+    cli_level: Option<LevelFilter>,
+    output: Mutex<Option<RotatingFile>>,
+    recent: Mutex<VecDeque<String>>,
+}
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// <data dir>/pycatai-pet/logs, ví dụ %LOCALAPPDATA% hoặc ~/.local/share
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join(APP_DIR).join("logs"))
+}
+
+// Gọi một lần, trước mọi dòng log; cấu hình từng module áp dụng sau khi đọc
+// config.toml (xem apply_config)
+pub fn init(cli_level: Option<LevelFilter>) {
+    let output = log_dir().and_then(|dir| match RotatingFile::open(&dir) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("Logging to stderr only, cannot open {}: {:#}", dir.display(), e);
+            None
+        }
+    });
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        filter: RwLock::new(build_filter(&LoggingConfig::default(), cli_level)),
+        cli_level,
+        output: Mutex::new(output),
+        recent: Mutex::new(VecDeque::with_capacity(RECENT_LINES)),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.max_level());
+    }
+    install_panic_hook();
+}
+
+pub fn apply_config(config: &LoggingConfig) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Ok(mut filter) = logger.filter.write() {
+        *filter = build_filter(config, logger.cli_level);
+    }
+    log::set_max_level(logger.max_level());
+}
+
+// RUST_LOG vẫn dùng được và thắng cả config lẫn --log-level
+fn build_filter(config: &LoggingConfig, cli_level: Option<LevelFilter>) -> Filter {
+    let mut builder = FilterBuilder::new();
+    builder.filter_level(cli_level.or_else(|| config.level.parse().ok()).unwrap_or(LevelFilter::Info));
+    for (module, level) in &config.modules {
+        if let Ok(level) = level.parse() {
+            builder.filter_module(module, level);
+        }
+    }
+    if let Ok(spec) = std::env::var("RUST_LOG") {
+        builder.parse(&spec);
+    }
+    builder.build()
+}
+
+// Che chuỗi này trong mọi dòng log, ví dụ API key đang dùng
+pub fn add_secret(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    if let Ok(mut secrets) = SECRETS.write() {
+        if !secrets.iter().any(|known| known == secret) {
+            secrets.push(secret.to_string());
+        }
+    }
+}
+
+// Che các bí mật đã biết, tham số `key=` trong URL và token sau "Bearer "
+pub fn redact(message: &str) -> String {
+    let mut redacted = message.to_string();
+    if let Ok(secrets) = SECRETS.read() {
+        for secret in secrets.iter() {
+            redacted = redacted.replace(secret.as_str(), REDACTED);
+        }
+    }
+    for marker in ["key=", "Bearer "] {
+        redacted = redact_after(&redacted, marker);
+    }
+    redacted
+}
+
+fn redact_after(message: &str, marker: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(index) = rest.find(marker) {
+        let (before, after) = rest.split_at(index + marker.len());
+        result.push_str(before);
+        let end = after
+            .find(|c: char| c.is_whitespace() || matches!(c, '&' | '"' | '\'' | ',' | ')'))
+            .unwrap_or(after.len());
+        if end > 0 && !after[..end].starts_with(REDACTED) {
+            result.push_str(REDACTED);
+        } else {
+            result.push_str(&after[..end]);
+        }
+        rest = &after[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+// Một dòng logfmt: time=... level=... target=... msg="..."
+fn format_line(record: &Record) -> String {
+    let message = redact(&record.args().to_string());
+    format!(
+        "time={} level={} target={} msg={}",
+        Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+        record.level().as_str().to_lowercase(),
+        record.target(),
+        quote(&message)
+    )
+}
+
+fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+impl FileLogger {
+    fn max_level(&self) -> LevelFilter {
+        self.filter.read().map(|filter| filter.filter()).unwrap_or(LevelFilter::Info)
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().is_ok_and(|filter| filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.read().is_ok_and(|filter| filter.matches(record)) {
+            return;
+        }
+        let line = format_line(record);
+        eprintln!("{}", line);
+        if record.level() == Level::Error {
+            metrics::set_last_error(&redact(&record.args().to_string()));
+        }
+
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() == RECENT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.clone());
+        }
+        if let Ok(mut output) = self.output.lock() {
+            if let Some(file) = output.as_mut() {
+                if let Err(e) = file.write_line(&line) {
+                    eprintln!("Failed to write log file: {}", e);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut output) = self.output.lock() {
+            if let Some(file) = output.as_mut() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, file, written })
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        if self.written + line.len() as u64 + 1 > MAX_LOG_BYTES && self.written > 0 {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    // pycatai-pet.log -> .1 -> .2 ...; file cũ nhất bị xoá
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        for index in (1..KEPT_FILES).rev() {
+            let from = numbered(&self.path, index);
+            if from.exists() {
+                std::fs::rename(&from, numbered(&self.path, index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, numbered(&self.path, 1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn numbered(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+// Ghi crash-<thời gian>.txt cạnh file log rồi gọi hook mặc định
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = crash_report(&info.to_string(), &std::backtrace::Backtrace::force_capture().to_string());
+        if let Some(dir) = log_dir() {
+            let path = dir.join(format!("crash-{}.txt", Local::now().format("%Y%m%d-%H%M%S")));
+            match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, report)) {
+                Ok(()) => eprintln!("Crash report written to {}", path.display()),
+                Err(e) => eprintln!("Failed to write crash report {}: {}", path.display(), e),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+fn crash_report(panic: &str, backtrace: &str) -> String {
+    let recent: Vec<String> = LOGGER
+        .get()
+        .and_then(|logger| logger.recent.lock().ok().map(|recent| recent.iter().cloned().collect()))
+        .unwrap_or_default();
+    format!(
+        "pyCatAI-pet {} crashed at {}\n\n{}\n\nBacktrace:\n{}\n\nLast {} log lines:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        redact(panic),
+        backtrace,
+        recent.len(),
+        recent.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        add_secret("sk-test-0123456789");
+        add_secret("short");
+        assert_eq!(
+            redact("POST https://example.com/v1?key=AIzaSecret&alt=json with sk-test-0123456789"),
+            "POST https://example.com/v1?key=[redacted]&alt=json with [redacted]"
+        );
+        assert_eq!(redact("Authorization: Bearer abc.def"), "Authorization: Bearer [redacted]");
+        assert_eq!(redact("a short story"), "a short story");
+        assert_eq!(quote("say \"hi\"\nbye"), "\"say \\\"hi\\\"\\nbye\"");
+    }
+
+    #[test]
+    fn test_log_file_rotates() {
+        let dir = std::env::temp_dir().join(format!("pycatai-logs-{}", std::process::id()));
+        let mut file = RotatingFile::open(&dir).unwrap();
+        let line = "x".repeat(1023);
+        for _ in 0..(KEPT_FILES + 2) * 1024 {
+            file.write_line(&line).unwrap();
+        }
+        assert!(dir.join(LOG_FILE).exists());
+        assert!(numbered(&dir.join(LOG_FILE), KEPT_FILES).exists());
+        assert!(!numbered(&dir.join(LOG_FILE), KEPT_FILES + 1).exists());
+        assert!(std::fs::metadata(dir.join(LOG_FILE)).unwrap().len() <= MAX_LOG_BYTES);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_module_levels() {
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            modules: std::collections::BTreeMap::from([("pycatai_pet_rust::speech".to_string(), "debug".to_string())]),
+        };
+        let filter = build_filter(&config, None);
+        let enabled = |target: &str, level| filter.enabled(&Metadata::builder().target(target).level(level).build());
+        assert!(enabled("pycatai_pet_rust::speech", log::Level::Debug));
+        assert!(!enabled("pycatai_pet_rust::display", log::Level::Info));
+        assert!(build_filter(&config, Some(LevelFilter::Info)).enabled(
+            &Metadata::builder().target("pycatai_pet_rust::display").level(log::Level::Info).build()
+        ));
+    }
+}
@@ -1,42 +1,186 @@
+mod audio;
+mod cli;
 mod display;
 mod sprite_handler;
+mod sprite_pack;
 mod window_handler;
+#[cfg(windows)]
+mod window_win32;
+#[cfg(target_os = "linux")]
+mod window_x11;
 mod comment_generator;
+mod config;
+mod events;
+mod instance;
+mod logging;
+mod metrics;
+mod notify;
+mod simulation;
+mod sounds;
+#[cfg(test)]
+mod render;
+#[cfg(feature = "piper")]
+mod piper;
+mod screen_source;
+mod settings;
+mod speech;
+mod speech_bubble;
+mod state;
+mod timing;
+mod tray;
+#[cfg(target_os = "linux")]
+mod tray_ksni;
+#[cfg(windows)]
+mod tray_win32;
 
-use anyhow::Result;
-use display::Display;
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use cli::{Cli, Command};
+use comment_generator::Commenter;
+use config::Config;
+use display::{Display, Flags};
+use instance::Instance;
 use log::{error, info};
+use screen_source::ImageFile;
+use simulation::Simulation;
+use sounds::SoundPack;
+use sprite_pack::SpritePack;
+use state::PetState;
+use tray::TrayMenu;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Khởi tạo logging
-    env_logger::init();
+    let cli = Cli::parse();
+    
+    // Khởi tạo logging; mức theo module đọc từ config.toml ngay sau đó
+    logging::init(cli.log_level);
     info!("Starting pyCatAI-pet Rust version");
     
+    let config_path = cli.config.clone().or_else(Config::path);
+    let mut config = match &config_path {
+        Some(path) => Config::load_or_default(path),
+        None => {
+            error!("No config directory on this platform, using defaults");
+            Config::default()
+        }
+    };
+    let overrides = cli.overrides();
+    overrides.apply(&mut config);
+    config.validate().map_err(|e| anyhow!("Invalid command-line options:\n{}", e))?;
+    logging::apply_config(&config.logging);
+    
+    match cli.command {
+        Some(Command::CommentOnce { image }) => {
+            let mut commenter = Commenter::new(&config.comments, cli.seed);
+            if let Some(path) = image {
+                commenter = commenter.with_screen_source(ImageFile::new(&path)?);
+            }
+            let comment = commenter.generate_comment();
+            println!("{}", comment);
+            return Ok(());
+        }
+        Some(Command::ValidateSprites { dir }) => {
+            let pack = SpritePack::load(&dir)
+                .map_err(|e| anyhow!("Invalid sprite pack {}:\n{}", dir.display(), e))?;
+            let sounds = SoundPack::load(&dir)
+                .map_err(|e| anyhow!("Invalid sounds in {}:\n{}", dir.display(), e))?;
+            for line in pack.summary().into_iter().chain(sounds.summary()) {
+                println!("{}", line);
+            }
+            println!("Sprite pack {} is valid", dir.display());
+            return Ok(());
+        }
+        Some(Command::ListVoices) => {
+            for (name, language) in speech::list_voices()? {
+                println!("{} ({})", name, language);
+            }
+            return Ok(());
+        }
+        Some(Command::Settings) => {
+            let path = config_path.ok_or_else(|| anyhow!("No config directory on this platform, use --config"))?;
+            return settings::run(path);
+        }
+        Some(Command::Control { command }) => {
+            let path = config_path.ok_or_else(|| anyhow!("No config directory on this platform, use --config"))?;
+            let reply = instance::send_command(&path, command)?;
+            if !reply.ok {
+                return Err(anyhow!("{}", reply.error.unwrap_or_default()));
+            }
+            if let Some(status) = reply.status {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            }
+            return Ok(());
+        }
+        Some(Command::Notify { command }) => {
+            let exit_code = notify::run(config_path.as_deref(), &command)?;
+            log::logger().flush();
+            std::process::exit(exit_code);
+        }
+        Some(Command::Simulate { seconds, screen, windows }) => {
+            // Mặc định dùng seed 0 để hai lần chạy cho cùng trace
+            let mut simulation = Simulation::new(&config, Some(cli.seed.unwrap_or(0)), screen, windows);
+            let duration = std::time::Duration::try_from_secs_f64(seconds)
+                .map_err(|e| anyhow!("Invalid --seconds: {}", e))?;
+            let stdout = std::io::stdout();
+            simulation.run(duration, &mut std::io::BufWriter::new(stdout.lock()))?;
+            return Ok(());
+        }
+        None => {}
+    }
+    
+    // Một con mèo cho mỗi file cấu hình; lần chạy sau chỉ chuyển tham số
+    let instance = match &config_path {
+        Some(path) => {
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            match Instance::acquire(path, &args)? {
+                Some(instance) => Some(std::sync::Arc::new(instance)),
+                None => {
+                    println!("pyCatAI-pet is already running, passed the options to it");
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+    
+    // Trạng thái lần chạy trước, nằm cạnh file cấu hình
+    let state_path = config_path.as_deref().map(PetState::path_beside);
+    let state = state_path.as_deref().filter(|path| path.exists()).and_then(|path| {
+        PetState::load(path).map_err(|e| error!("{:#}, starting fresh", e)).ok()
+    });
+    if let Some(state) = &state {
+        let modified = config_path.as_deref().and_then(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok());
+        state.restore_settings(&mut config, modified);
+        overrides.apply(&mut config);
+    }
+    
+    // Biểu tượng khay hệ thống, tạo một lần ở đây vì Display được dựng hai lần
+    let skins_dir = config_path.as_deref().map(|path| path.with_file_name(tray::SKINS_DIR));
+    let skins = tray::skins(&config.display.sprite_dir, skins_dir.as_deref());
+    let tray = tray::spawn(TrayMenu::default());
+    
+    let flags = Flags {
+        config,
+        config_path,
+        overrides,
+        seed: cli.seed,
+        state_path,
+        state,
+        instance,
+        shutdown: instance::shutdown_signal(),
+        tray,
+        skins,
+    };
+    
     // Khởi chạy ứng dụng
-    match Display::new().run() {
+    match Display::new(flags).run() {
         Ok(_) => {
             info!("Application closed successfully");
             Ok(())
         }
         Err(e) => {
-            error!("Application error: {}", e);
-            // Ghi lỗi vào file error log
-            use std::fs::OpenOptions;
-            use std::io::Write;
-            use chrono::Local;
-            
-            let now = Local::now();
-            let error_message = format!("{}: {}\n", now.format("%Y-%m-%d %H:%M:%S"), e);
-            
-            let mut file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open("errorlog.txt")?;
-                
-            file.write_all(error_message.as_bytes())?;
-            
-            Err(e.into())
+            error!("Application error: {:#}", e);
+            Err(e)
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,179 @@
+// Bộ đếm và histogram trong bộ nhớ: độ trễ API, lỗi theo loại, số lần dùng
+// câu có sẵn, kích thước ảnh chụp, lỗi TTS và thời gian mỗi frame. Hiện trên
+// bảng debug cạnh mèo (phím D) và ghi vào log khi thoát.
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+pub const API_OK: &str = "api.ok";
+// Theo sau là loại lỗi, ví dụ api.failures.timeout
+pub const API_FAILURES: &str = "api.failures";
+pub const API_LATENCY_MS: &str = "api.latency_ms";
+pub const SCREENSHOT_BYTES: &str = "screenshot.bytes";
+pub const FALLBACKS: &str = "comments.fallback";
+// Bình luận do sự kiện gây ra, theo sau là loại, ví dụ comments.events.app_switch
+pub const EVENT_COMMENTS: &str = "comments.events";
+pub const TTS_FAILURES: &str = "tts.failures";
+pub const FRAME_MS: &str = "frame.ms";
+
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    counters: BTreeMap<String, u64>,
+    histograms: BTreeMap<String, Histogram>,
+    last_error: Option<(DateTime<Local>, String)>,
+}
+
+// Bucket thứ i đếm các giá trị trong (2^(i-1), 2^i]; đủ để ước lượng p50/p95
+// của cả mili giây lẫn byte mà không phải giữ từng giá trị
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    count: u64,
+    sum: f64,
+    max: f64,
+    buckets: Vec<u64>,
+}
+
+pub fn increment(name: &str) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.increment(name);
+    }
+}
+
+pub fn observe(name: &str, value: f64) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.observe(name, value);
+    }
+}
+
+// Gọi từ logger cho mỗi dòng log mức error
+pub fn set_last_error(message: &str) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.last_error = Some((Local::now(), message.to_string()));
+    }
+}
+
+pub fn snapshot() -> Metrics {
+    METRICS.lock().map(|metrics| metrics.clone()).unwrap_or_default()
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            counters: BTreeMap::new(),
+            histograms: BTreeMap::new(),
+            last_error: None,
+        }
+    }
+
+    pub fn increment(&mut self, name: &str) {
+        *self.counters.entry(name.to_string()).or_default() += 1;
+    }
+
+    pub fn observe(&mut self, name: &str, value: f64) {
+        self.histograms.entry(name.to_string()).or_default().record(value);
+    }
+
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters.get(name).copied().unwrap_or(0)
+    }
+
+    // Các bộ đếm bắt đầu bằng `prefix.`, ví dụ từng loại lỗi API
+    pub fn counters_under(&self, prefix: &str) -> Vec<(&str, u64)> {
+        self.counters
+            .iter()
+            .filter_map(|(name, count)| Some((name.strip_prefix(prefix)?.strip_prefix('.')?, *count)))
+            .collect()
+    }
+
+    pub fn histogram(&self, name: &str) -> Option<&Histogram> {
+        self.histograms.get(name)
+    }
+
+    pub fn last_error(&self) -> Option<&(DateTime<Local>, String)> {
+        self.last_error.as_ref()
+    }
+
+    // Mỗi số liệu một dòng, để ghi log
+    pub fn summary(&self) -> Vec<String> {
+        let counters = self.counters.iter().map(|(name, count)| format!("{}={}", name, count));
+        let histograms = self.histograms.iter().map(|(name, histogram)| {
+            format!(
+                "{} count={} mean={:.1} p50<={} p95<={} max={:.1}",
+                name,
+                histogram.count(),
+                histogram.mean(),
+                histogram.quantile(0.5),
+                histogram.quantile(0.95),
+                histogram.max
+            )
+        });
+        counters.chain(histograms).collect()
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: f64) {
+        let value = value.max(0.0);
+        let index = value.max(1.0).log2().ceil() as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    // Cận trên của bucket chứa phân vị `q`, không vượt quá giá trị lớn nhất
+    pub fn quantile(&self, q: f64) -> f64 {
+        let target = (q * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return 2f64.powi(index as i32).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_histograms() {
+        let mut metrics = Metrics::default();
+        metrics.increment(API_OK);
+        metrics.increment("api.failures.timeout");
+        metrics.increment("api.failures.timeout");
+        metrics.increment("api.failures.http");
+        assert_eq!(metrics.counter(API_OK), 1);
+        assert_eq!(metrics.counters_under(API_FAILURES), vec![("http", 1), ("timeout", 2)]);
+
+        for ms in [120.0, 300.0, 450.0, 800.0, 5000.0] {
+            metrics.observe(API_LATENCY_MS, ms);
+        }
+        let latency = metrics.histogram(API_LATENCY_MS).unwrap();
+        assert_eq!(latency.count(), 5);
+        assert_eq!(latency.mean(), 1334.0);
+        assert_eq!(latency.quantile(0.5), 512.0);
+        assert_eq!(latency.quantile(0.95), 5000.0);
+        assert!(metrics.summary().contains(&"api.failures.timeout=2".to_string()));
+    }
+}
@@ -0,0 +1,71 @@
+// `pycatai-pet notify -- <lệnh>`: chạy lệnh (build, test...), rồi báo kết quả
+// cho con mèo đang chạy qua API điều khiển. Mã thoát của lệnh được giữ
+// nguyên để script và CI vẫn thấy lỗi; mèo không chạy thì chỉ ghi log.
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::ControlCommand;
+use crate::instance;
+
+// Giống shell khi không tìm thấy hoặc không chạy được lệnh
+const NOT_RUNNABLE: i32 = 127;
+// Tên lệnh trong câu của mèo, đủ ngắn để vừa khung chat
+const MAX_NAME_CHARS: usize = 40;
+
+// Mã thoát của lệnh, để main thoát với cùng mã đó
+pub fn run(config_path: Option<&Path>, command: &[String]) -> Result<i32> {
+    let (program, args) = command.split_first().ok_or_else(|| anyhow!("No command to run"))?;
+    let exit_code = match Command::new(program).args(args).status() {
+        Ok(status) => status.code(),
+        Err(e) => {
+            error!("Failed to run {}: {}", program, e);
+            Some(NOT_RUNNABLE)
+        }
+    };
+    info!("{} finished with exit code {:?}", describe(command), exit_code);
+
+    let finished = ControlCommand::Finished { command: describe(command), exit_code };
+    match config_path.map(|path| instance::send_command(path, finished)) {
+        Some(Ok(reply)) if !reply.ok => info!("The cat did not react: {}", reply.error.unwrap_or_default()),
+        Some(Err(e)) => info!("The cat did not react: {:#}", e),
+        _ => {}
+    }
+    // Bị tín hiệu dừng thì không có mã thoát
+    Ok(exit_code.unwrap_or(1))
+}
+
+// "cargo build" thay vì đường dẫn đầy đủ của chương trình
+fn describe(command: &[String]) -> String {
+    let mut words = command.iter().map(String::as_str);
+    let program = words.next().map(|program| {
+        PathBuf::from(program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| program.to_string())
+    });
+    let name = program.into_iter().chain(words.map(str::to_string)).collect::<Vec<_>>().join(" ");
+    match name.char_indices().nth(MAX_NAME_CHARS) {
+        Some((end, _)) => format!("{}…", &name[..end]),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_and_exit_code() {
+        let command = |words: &[&str]| words.iter().map(|word| word.to_string()).collect::<Vec<_>>();
+        assert_eq!(describe(&command(&["/usr/bin/cargo", "build", "--release"])), "cargo build --release");
+        assert_eq!(describe(&command(&["make", &"x".repeat(60)])).chars().count(), MAX_NAME_CHARS + 1);
+
+        // Không có con mèo nào đang chạy: mã thoát vẫn được giữ
+        let config_path = std::env::temp_dir().join(format!("pycatai-notify-{}", std::process::id())).join("config.toml");
+        assert_eq!(run(Some(&config_path), &command(&["pycatai-no-such-program"])).unwrap(), NOT_RUNNABLE);
+        #[cfg(unix)]
+        assert_eq!(run(Some(&config_path), &command(&["sh", "-c", "exit 3"])).unwrap(), 3);
+    }
+}
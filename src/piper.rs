@@ -0,0 +1,230 @@
+// Giọng đọc neural chạy cục bộ bằng Piper (https://github.com/rhasspy/piper):
+// tổng hợp file WAV từ model .onnx trên đĩa rồi phát bằng trình phát của hệ
+// thống, không cần dịch vụ cloud. Chỉ có khi build với `--features piper`.
+use anyhow::{anyhow, Context, Result};
+use log::{error, info};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+use crate::audio::{self, Clip};
+use crate::config::SpeechConfig;
+use crate::metrics;
+use crate::speech::SpeechBackend;
+
+pub struct PiperSpeech {
+    executable: PathBuf,
+    model: PathBuf,
+    speaker: Option<u32>,
+    // Command that plays a WAV file, which is appended as the last argument
+    player: Vec<String>,
+    length_scale: f32,
+    volume: f32,
+    output: PathBuf,
+    // Tiến trình đang chạy; cả hai bước được theo dõi bằng try_wait để
+    // không chặn luồng giao diện
+    stage: Option<Stage>,
+}
+
+enum Stage {
+    Synthesizing(Child),
+    Playing(Child),
+}
+
+impl PiperSpeech {
+    pub fn new(config: &SpeechConfig) -> Result<Self> {
+        let model = config
+            .piper_model
+            .clone()
+            .ok_or_else(|| anyhow!("speech.backend = \"piper\" needs speech.piper_model"))?;
+        if !model.is_file() {
+            return Err(anyhow!("Piper voice model {} does not exist", model.display()));
+        }
+
+        let mut speech = Self {
+            executable: config.piper_executable.clone(),
+            model,
+            speaker: config.piper_speaker,
+            player: audio::player_command(),
+            length_scale: 1.0,
+            volume: 1.0,
+            output: std::env::temp_dir().join(format!("pycatai-speech-{}.wav", std::process::id())),
+            stage: None,
+        };
+        speech.set_voice(config)?;
+        info!("Using Piper voice {}", speech.model.display());
+        Ok(speech)
+    }
+
+    // Chạy Piper nền, không chờ nó xong
+    fn synthesize(&self, text: &str) -> Result<Child> {
+        let mut command = Command::new(&self.executable);
+        command
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--output_file")
+            .arg(&self.output)
+            .arg("--length_scale")
+            .arg(self.length_scale.to_string());
+        if let Some(speaker) = self.speaker {
+            command.arg("--speaker").arg(speaker.to_string());
+        }
+
+        // Piper đọc văn bản từ stdin
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Cannot run Piper ({})", self.executable.display()))?;
+        // Đóng stdin để Piper biết đã hết văn bản
+        let written = child
+            .stdin
+            .take()
+            .context("piper stdin")
+            .and_then(|mut stdin| stdin.write_all(text.as_bytes()).context("piper stdin"));
+        if let Err(e) = written {
+            // Piper đã thoát sớm (ví dụ model hỏng): thu dọn tiến trình
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+        Ok(child)
+    }
+
+    // Piper đã ghi xong file WAV: chỉnh âm lượng rồi phát
+    fn play(&self) -> Result<Child> {
+        if self.volume < 1.0 {
+            Clip::load(&self.output)?.write_wav(&self.output, self.volume)?;
+        }
+        audio::spawn_player(&self.player, &self.output)
+    }
+}
+
+impl SpeechBackend for PiperSpeech {
+    fn speak(&mut self, text: &str) -> Result<()> {
+        self.stop()?;
+        self.stage = Some(Stage::Synthesizing(self.synthesize(text)?));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if let Some(Stage::Synthesizing(mut child) | Stage::Playing(mut child)) = self.stage.take() {
+            // The process may have exited already
+            let _ = child.kill();
+            child.wait()?;
+        }
+        Ok(())
+    }
+
+    // Gọi định kỳ: chuyển từ tổng hợp sang phát khi Piper xong
+    fn is_speaking(&mut self) -> bool {
+        let next = match self.stage.as_mut() {
+            None => return false,
+            Some(Stage::Synthesizing(child)) => match child.try_wait() {
+                Ok(None) => return true,
+                Ok(Some(status)) if status.success() => self.play().map(Stage::Playing),
+                Ok(Some(status)) => Err(anyhow!("Piper failed: {}", status)),
+                Err(e) => Err(e.into()),
+            },
+            Some(Stage::Playing(child)) => match child.try_wait() {
+                Ok(None) => return true,
+                _ => {
+                    self.stage = None;
+                    return false;
+                }
+            },
+        };
+        match next {
+            Ok(stage) => {
+                self.stage = Some(stage);
+                true
+            }
+            Err(e) => {
+                error!("TTS failed: {:#}", e);
+                metrics::increment(metrics::TTS_FAILURES);
+                self.stage = None;
+                false
+            }
+        }
+    }
+
+    // Piper has no pitch control; rate maps to the phoneme length
+    fn set_voice(&mut self, config: &SpeechConfig) -> Result<()> {
+        self.length_scale = 1.0 / config.rate;
+        self.volume = config.volume;
+        Ok(())
+    }
+}
+
+impl Drop for PiperSpeech {
+    fn drop(&mut self) {
+        let _ = self.stop();
+        let _ = std::fs::remove_file(&self.output);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_piper_synthesizes_and_plays() {
+        let dir = std::env::temp_dir().join(format!("pycatai-piper-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 22050, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut fixture = hound::WavWriter::create(dir.join("fixture.wav"), spec).unwrap();
+        fixture.write_sample(1000i16).unwrap();
+        fixture.finalize().unwrap();
+        std::fs::write(dir.join("voice.onnx"), "").unwrap();
+
+        // Piper giả: ghi lại tham số và văn bản, trả về file WAV mẫu
+        let script = dir.join("piper");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {dir}/args\ncat > {dir}/text\nsleep 0.3\nwhile [ $# -gt 0 ]; do [ \"$1\" = --output_file ] && cp {dir}/fixture.wav \"$2\"; shift; done\n",
+                dir = dir.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = SpeechConfig {
+            rate: 2.0,
+            volume: 0.5,
+            piper_executable: script,
+            piper_model: Some(dir.join("voice.onnx")),
+            ..SpeechConfig::default()
+        };
+        let mut speech = PiperSpeech::new(&config).unwrap();
+        speech.player = vec!["sh".to_string(), "-c".to_string(), "sleep 0.2".to_string(), "player".to_string()];
+
+        // Tổng hợp chạy nền: speak trả về ngay, mèo vẫn chuyển động
+        let started = std::time::Instant::now();
+        speech.speak("Meow!").unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_millis(300));
+        while matches!(speech.stage, Some(Stage::Synthesizing(_))) {
+            assert!(speech.is_speaking());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(speech.is_speaking());
+        assert_eq!(std::fs::read_to_string(dir.join("text")).unwrap(), "Meow!");
+        assert!(std::fs::read_to_string(dir.join("args")).unwrap().contains("--length_scale 0.5"));
+        let samples: Vec<i16> = hound::WavReader::open(&speech.output).unwrap().samples().map(Result::unwrap).collect();
+        assert_eq!(samples, vec![500]);
+
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        assert!(!speech.is_speaking());
+
+        // Piper thoát ngay mà không đọc văn bản: báo lỗi thay vì treo
+        std::fs::write(&speech.executable, "#!/bin/sh\nexit 1\n").unwrap();
+        let error = speech.speak(&"Meow! ".repeat(100_000)).unwrap_err();
+        assert!(error.to_string().contains("piper stdin"), "{:#}", error);
+        assert!(!speech.is_speaking());
+
+        drop(speech);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
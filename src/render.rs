@@ -0,0 +1,165 @@
+// Vẽ offscreen cửa sổ mèo thành ảnh RGBA theo đúng bố cục của
+// SpriteController::view, dùng cho golden-image test (chỉ build khi test)
+use iced::Color;
+use image::{imageops, DynamicImage, RgbaImage};
+
+use crate::sprite_handler::sprite_rect;
+use crate::window_handler::WindowRect;
+
+// Source-over blend of `color` into one pixel
+pub fn blend(image: &mut RgbaImage, x: i32, y: i32, color: Color) {
+    if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+        return;
+    }
+
+    let src_a = color.a;
+    if src_a <= 0.0 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let dst = pixel.0.map(|c| c as f32 / 255.0);
+    let dst_a = dst[3];
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    let channel = |src: f32, dst: f32| (src * src_a + dst * dst_a * (1.0 - src_a)) / out_a;
+
+    pixel.0 = [
+        (channel(color.r, dst[0]) * 255.0).round() as u8,
+        (channel(color.g, dst[1]) * 255.0).round() as u8,
+        (channel(color.b, dst[2]) * 255.0).round() as u8,
+        (out_a * 255.0).round() as u8,
+    ];
+}
+
+pub fn fill_rect(image: &mut RgbaImage, rect: WindowRect, color: Color) {
+    for y in rect.top..rect.bottom {
+        for x in rect.left..rect.right {
+            blend(image, x, y, color);
+        }
+    }
+}
+
+// Hình chữ nhật bo góc, tô theo tâm pixel
+pub fn fill_rounded_rect(image: &mut RgbaImage, rect: WindowRect, radius: f32, color: Color) {
+    let r = radius.min(rect.width() as f32 / 2.0).min(rect.height() as f32 / 2.0);
+    let (left, top) = (rect.left as f32 + r, rect.top as f32 + r);
+    let (right, bottom) = (rect.right as f32 - r, rect.bottom as f32 - r);
+
+    for y in rect.top..rect.bottom {
+        for x in rect.left..rect.right {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            // Distance from the nearest corner circle centre, zero along the edges
+            let dx = (left - px).max(px - right).max(0.0);
+            let dy = (top - py).max(py - bottom).max(0.0);
+            if dx * dx + dy * dy <= r * r {
+                blend(image, x, y, color);
+            }
+        }
+    }
+}
+
+pub fn fill_triangle(image: &mut RgbaImage, points: [(f32, f32); 3], color: Color) {
+    let [a, b, c] = points;
+    let edge = |p: (f32, f32), q: (f32, f32), x: f32, y: f32| (q.0 - p.0) * (y - p.1) - (q.1 - p.1) * (x - p.0);
+
+    let min_x = a.0.min(b.0).min(c.0).floor() as i32;
+    let max_x = a.0.max(b.0).max(c.0).ceil() as i32;
+    let min_y = a.1.min(b.1).min(c.1).floor() as i32;
+    let max_y = a.1.max(b.1).max(c.1).ceil() as i32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let (e0, e1, e2) = (edge(a, b, px, py), edge(b, c, px, py), edge(c, a, px, py));
+            if (e0 >= 0.0 && e1 >= 0.0 && e2 >= 0.0) || (e0 <= 0.0 && e1 <= 0.0 && e2 <= 0.0) {
+                blend(image, x, y, color);
+            }
+        }
+    }
+}
+
+pub fn draw_sprite(image: &mut RgbaImage, frame: &DynamicImage, bounds: WindowRect) {
+    let target = sprite_rect(frame.width(), frame.height(), bounds);
+    if target.width() <= 0 || target.height() <= 0 {
+        return;
+    }
+
+    // iced samples sprites with linear filtering
+    let scaled = imageops::resize(
+        &frame.to_rgba8(),
+        target.width() as u32,
+        target.height() as u32,
+        imageops::FilterType::Triangle,
+    );
+    for (x, y, pixel) in scaled.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0.map(|c| c as f32 / 255.0);
+        blend(image, target.left + x as i32, target.top + y as i32, Color::from_rgba(r, g, b, a));
+    }
+}
+
+// Số pixel khác nhau quá `tolerance` ở bất kỳ kênh màu nào
+pub fn diff(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> usize {
+    if a.dimensions() != b.dimensions() {
+        return usize::MAX;
+    }
+    a.pixels()
+        .zip(b.pixels())
+        .filter(|(p, q)| p.0.iter().zip(q.0.iter()).any(|(x, y)| x.abs_diff(*y) > tolerance))
+        .count()
+}
+
+// Compares `actual` with tests/golden/<name>.png. Set UPDATE_GOLDEN=1 to
+// (re)write the golden files after an intended rendering change.
+pub fn assert_golden(name: &str, actual: &RgbaImage) {
+    // Allow small filtering differences, but not a moved or missing shape
+    const CHANNEL_TOLERANCE: u8 = 8;
+    const MAX_DIFFERENT_PIXELS: f32 = 0.002;
+
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let path = dir.join(format!("{}.png", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(&dir).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| panic!("missing golden {} ({}); run with UPDATE_GOLDEN=1", path.display(), e))
+        .to_rgba8();
+    let different = diff(&expected, actual, CHANNEL_TOLERANCE);
+    let allowed = (actual.width() * actual.height()) as f32 * MAX_DIFFERENT_PIXELS;
+
+    if different as f32 > allowed {
+        // Keep the actual output around for inspection
+        let failed = std::env::temp_dir().join(format!("pycatai-golden-{}.png", name));
+        actual.save(&failed).unwrap();
+        panic!(
+            "{} differs from golden in {} pixels (allowed {}); actual image saved to {}",
+            name,
+            different,
+            allowed as usize,
+            failed.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend_and_diff() {
+        let mut image = RgbaImage::new(4, 4);
+        fill_rect(&mut image, WindowRect::new(0, 0, 2, 2), Color::from_rgba(1.0, 0.0, 0.0, 0.5));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0, 0]);
+
+        let blank = RgbaImage::new(4, 4);
+        let mut rounded = blank.clone();
+        fill_rounded_rect(&mut rounded, WindowRect::new(0, 0, 4, 4), 2.0, Color::WHITE);
+        // Bốn góc bo tròn không bị tô
+        assert_eq!(rounded.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(rounded.get_pixel(1, 1).0, [255, 255, 255, 255]);
+        assert_eq!(diff(&blank, &rounded, 8), 12);
+    }
+}
@@ -0,0 +1,179 @@
+// Nguồn ảnh để mèo bình luận: màn hình thật, một file ảnh, lần lượt các ảnh
+// trong một thư mục, hoặc ảnh mẫu tự tạo (dùng khi không có màn hình)
+use anyhow::{anyhow, Result};
+use image::{Rgba, RgbaImage};
+use log::info;
+use screenshots::Screen;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::config::{CommentConfig, ScreenSourceKind};
+
+pub trait ScreenSource: Send + Sync {
+    fn capture(&self) -> Result<RgbaImage>;
+}
+
+// Closures work as sources, handy for tests and one-off images
+impl<F> ScreenSource for F
+where
+    F: Fn() -> Result<RgbaImage> + Send + Sync,
+{
+    fn capture(&self) -> Result<RgbaImage> {
+        self()
+    }
+}
+
+// Ảnh chụp màn hình chính
+pub struct LiveScreen;
+
+impl ScreenSource for LiveScreen {
+    fn capture(&self) -> Result<RgbaImage> {
+        info!("Taking screenshot");
+        let screens = Screen::all()?;
+        let screen = screens.first().ok_or_else(|| anyhow!("No screens found"))?;
+        screen.capture()
+    }
+}
+
+// Một file ảnh, đọc lại mỗi lần để thấy được thay đổi
+pub struct ImageFile {
+    path: PathBuf,
+}
+
+impl ImageFile {
+    pub fn new(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(anyhow!("Image {} does not exist", path.display()));
+        }
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl ScreenSource for ImageFile {
+    fn capture(&self) -> Result<RgbaImage> {
+        let image = image::open(&self.path).map_err(|e| anyhow!("Cannot read image {}: {}", self.path.display(), e))?;
+        Ok(image.to_rgba8())
+    }
+}
+
+// Các ảnh trong thư mục theo thứ tự tên file, quay vòng
+pub struct ImageDirectory {
+    files: Vec<PathBuf>,
+    next: AtomicUsize,
+}
+
+impl ImageDirectory {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| anyhow!("Cannot read image directory {}: {}", dir.display(), e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok())
+            .collect();
+        if files.is_empty() {
+            return Err(anyhow!("Image directory {} has no images", dir.display()));
+        }
+        files.sort();
+        Ok(Self { files, next: AtomicUsize::new(0) })
+    }
+}
+
+impl ScreenSource for ImageDirectory {
+    fn capture(&self) -> Result<RgbaImage> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.files.len();
+        let path = &self.files[index];
+        let image = image::open(path).map_err(|e| anyhow!("Cannot read image {}: {}", path.display(), e))?;
+        Ok(image.to_rgba8())
+    }
+}
+
+// Ảnh mẫu: các dải màu và một ô vuông trắng đổi chỗ sau mỗi lần chụp
+pub struct TestPattern {
+    width: u32,
+    height: u32,
+    frame: AtomicUsize,
+}
+
+const PATTERN_COLORS: [[u8; 3]; 7] = [
+    [192, 192, 192],
+    [192, 192, 0],
+    [0, 192, 192],
+    [0, 192, 0],
+    [192, 0, 192],
+    [192, 0, 0],
+    [0, 0, 192],
+];
+
+impl TestPattern {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, frame: AtomicUsize::new(0) }
+    }
+}
+
+impl ScreenSource for TestPattern {
+    fn capture(&self) -> Result<RgbaImage> {
+        let frame = self.frame.fetch_add(1, Ordering::Relaxed) as u32;
+        let bar_width = (self.width / PATTERN_COLORS.len() as u32).max(1);
+        let square = self.height / 4;
+        let square_x = (frame * square) % self.width.saturating_sub(square).max(1);
+
+        Ok(RgbaImage::from_fn(self.width, self.height, |x, y| {
+            if x >= square_x && x < square_x + square && y >= square && y < 2 * square {
+                return Rgba([255, 255, 255, 255]);
+            }
+            let [r, g, b] = PATTERN_COLORS[((x / bar_width) as usize).min(PATTERN_COLORS.len() - 1)];
+            Rgba([r, g, b, 255])
+        }))
+    }
+}
+
+pub fn from_config(config: &CommentConfig) -> Result<Arc<dyn ScreenSource>> {
+    let path = || {
+        config
+            .screen_path
+            .as_deref()
+            .ok_or_else(|| anyhow!("screen_source = \"{}\" needs screen_path", config.screen_source.name()))
+    };
+    Ok(match config.screen_source {
+        ScreenSourceKind::Screen => Arc::new(LiveScreen),
+        ScreenSourceKind::File => Arc::new(ImageFile::new(path()?)?),
+        ScreenSourceKind::Directory => Arc::new(ImageDirectory::new(path()?)?),
+        ScreenSourceKind::Pattern => Arc::new(TestPattern::new(1280, 720)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_cycles_through_images() {
+        let dir = std::env::temp_dir().join(format!("pycatai-screens-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        RgbaImage::new(10, 10).save(dir.join("a.png")).unwrap();
+        RgbaImage::new(20, 10).save(dir.join("b.png")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+
+        let source = ImageDirectory::new(&dir).unwrap();
+        let widths: Vec<u32> = (0..3).map(|_| source.capture().unwrap().width()).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(widths, vec![10, 20, 10]);
+    }
+
+    #[test]
+    fn test_pattern_changes_between_captures() {
+        let source = TestPattern::new(320, 180);
+        let first = source.capture().unwrap();
+        assert_eq!(first.dimensions(), (320, 180));
+        assert_ne!(first, source.capture().unwrap());
+    }
+
+    #[test]
+    fn test_from_config_needs_a_path() {
+        let config = CommentConfig { screen_source: ScreenSourceKind::Directory, ..CommentConfig::default() };
+        let error = from_config(&config).err().unwrap().to_string();
+        assert!(error.contains("needs screen_path"));
+    }
+}
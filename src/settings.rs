@@ -0,0 +1,339 @@
+// Cửa sổ cài đặt: chạy như một tiến trình riêng (`pycatai-pet settings`), sửa
+// config.toml rồi lưu; con mèo đang chạy tự nạp lại file khi nó thay đổi.
+use anyhow::{anyhow, Result};
+use iced::widget::{button, column, container, image, pick_list, row, scrollable, slider, text, text_input, Column};
+use iced::{executor, window, Application, Command, Element, Length, Settings, Theme};
+use log::error;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::comment_generator;
+use crate::config::{Config, Provider};
+use crate::speech;
+use crate::tray;
+
+const WINDOW_SIZE: (u32, u32) = (560, 760);
+const SYSTEM_VOICE: &str = "System default";
+// Khoảng chọn được trên thanh trượt, tính bằng giây
+const INTERVAL_RANGE: std::ops::RangeInclusive<f64> = 10.0..=7200.0;
+const THUMBNAIL_SIZE: u16 = 96;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skin(PathBuf);
+
+impl fmt::Display for Skin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tray::skin_name(&self.0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Provider(Provider),
+    ApiKey(String),
+    Persona(String),
+    AverageInterval(f64),
+    MinInterval(f64),
+    MaxInterval(f64),
+    Voice(String),
+    Skin(Skin),
+    NewDenyTitle(String),
+    AddDenyTitle,
+    RemoveDenyTitle(usize),
+    Save,
+}
+
+pub struct SettingsWindow {
+    path: PathBuf,
+    config: Config,
+    personas: Vec<String>,
+    voices: Vec<String>,
+    skins: Vec<Skin>,
+    thumbnails: HashMap<PathBuf, image::Handle>,
+    new_deny_title: String,
+    status: String,
+    // config.toml có nhưng sai: không cho lưu, tránh ghi đè file bằng mặc định
+    load_error: Option<String>,
+}
+
+pub fn run(path: PathBuf) -> Result<()> {
+    let settings = Settings {
+        window: window::Settings {
+            size: WINDOW_SIZE,
+            ..Default::default()
+        },
+        flags: path,
+        ..Default::default()
+    };
+    SettingsWindow::run(settings).map_err(|e| anyhow!("Failed to run the settings window: {}", e))
+}
+
+impl SettingsWindow {
+    fn load(path: PathBuf) -> Self {
+        let (config, load_error) = if path.exists() {
+            match Config::load(&path) {
+                Ok(config) => (config, None),
+                Err(e) => (Config::default(), Some(format!("{:#}", e))),
+            }
+        } else {
+            (Config::default(), None)
+        };
+
+        let mut personas: Vec<String> = comment_generator::persona_names().into_iter().map(String::from).collect();
+        if !personas.contains(&config.comments.persona) {
+            personas.push(config.comments.persona.clone());
+        }
+
+        let mut voices = vec![SYSTEM_VOICE.to_string()];
+        match speech::list_voices() {
+            Ok(list) => voices.extend(list.into_iter().map(|(name, _)| name)),
+            Err(e) => error!("No system voices: {}", e),
+        }
+
+        let skin_dirs = tray::skins(&config.display.sprite_dir, Some(&path.with_file_name(tray::SKINS_DIR)));
+        let thumbnails = skin_dirs.iter().filter_map(|dir| Some((dir.clone(), thumbnail(dir)?))).collect();
+
+        Self {
+            path,
+            config,
+            personas,
+            voices,
+            skins: skin_dirs.into_iter().map(Skin).collect(),
+            thumbnails,
+            new_deny_title: String::new(),
+            status: load_error
+                .as_ref()
+                .map(|e| format!("Fix config.toml and reopen settings to save:\n{}", e))
+                .unwrap_or_default(),
+            load_error,
+        }
+    }
+
+    fn save(&mut self) {
+        if self.load_error.is_some() {
+            return;
+        }
+        self.status = match self.config.validate().and_then(|_| self.config.save(&self.path)) {
+            Ok(()) => format!("Saved to {}", self.path.display()),
+            Err(e) => format!("Not saved:\n{}", e),
+        };
+    }
+
+    fn persona_preview(&self) -> String {
+        comment_generator::persona_prompt(&self.config.comments.persona).unwrap_or_else(|e| e.to_string())
+    }
+}
+
+impl Application for SettingsWindow {
+    type Executor = executor::Default;
+    type Message = Message;
+    type Theme = Theme;
+    type Flags = PathBuf;
+
+    fn new(path: PathBuf) -> (Self, Command<Message>) {
+        (Self::load(path), Command::none())
+    }
+
+    fn title(&self) -> String {
+        String::from("pyCatAI-pet settings")
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let comments = &mut self.config.comments;
+        match message {
+            Message::Provider(provider) => comments.provider = provider,
+            Message::ApiKey(key) => comments.api_key = Some(key).filter(|key| !key.is_empty()),
+            Message::Persona(persona) => comments.persona = persona,
+            // Giữ min <= trung bình <= max khi kéo thanh trượt
+            Message::AverageInterval(secs) => {
+                comments.average_interval_secs = secs.clamp(comments.min_interval_secs, comments.max_interval_secs)
+            }
+            Message::MinInterval(secs) => {
+                comments.min_interval_secs = secs;
+                comments.average_interval_secs = comments.average_interval_secs.max(secs);
+                comments.max_interval_secs = comments.max_interval_secs.max(secs);
+            }
+            Message::MaxInterval(secs) => {
+                comments.max_interval_secs = secs;
+                comments.average_interval_secs = comments.average_interval_secs.min(secs);
+                comments.min_interval_secs = comments.min_interval_secs.min(secs);
+            }
+            Message::Voice(voice) => self.config.speech.voice = Some(voice).filter(|voice| voice != SYSTEM_VOICE),
+            Message::Skin(Skin(dir)) => self.config.display.sprite_dir = dir,
+            Message::NewDenyTitle(title) => self.new_deny_title = title,
+            Message::AddDenyTitle => {
+                let title = self.new_deny_title.trim().to_string();
+                if !title.is_empty() && !self.config.privacy.deny_titles.contains(&title) {
+                    self.config.privacy.deny_titles.push(title);
+                }
+                self.new_deny_title.clear();
+            }
+            Message::RemoveDenyTitle(index) => {
+                if index < self.config.privacy.deny_titles.len() {
+                    self.config.privacy.deny_titles.remove(index);
+                }
+            }
+            Message::Save => self.save(),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let comments = &self.config.comments;
+
+        let provider = row![
+            text("Provider").width(120),
+            pick_list(&[Provider::Gemini, Provider::OpenAi, Provider::Offline][..], Some(comments.provider), Message::Provider),
+        ]
+        .spacing(10);
+        let api_key = row![
+            text("API key").width(120),
+            text_input("From GEMINI_API_KEY / OPENAI_API_KEY", comments.api_key.as_deref().unwrap_or(""))
+                .on_input(Message::ApiKey)
+                .password(),
+        ]
+        .spacing(10);
+        let persona = row![
+            text("Persona").width(120),
+            pick_list(self.personas.as_slice(), Some(comments.persona.clone()), Message::Persona),
+        ]
+        .spacing(10);
+        let preview = container(text(self.persona_preview()).size(14)).padding(8).style(iced::theme::Container::Box);
+
+        let interval = |label: &str, secs: f64, on_change: fn(f64) -> Message| {
+            row![
+                text(label).width(120),
+                slider(INTERVAL_RANGE, secs, on_change).step(10.0),
+                text(format!("{:.0} min", secs / 60.0)).width(60),
+            ]
+            .spacing(10)
+        };
+
+        let voice = row![
+            text("Voice").width(120),
+            pick_list(
+                self.voices.as_slice(),
+                Some(self.config.speech.voice.clone().unwrap_or_else(|| SYSTEM_VOICE.to_string())),
+                Message::Voice,
+            ),
+        ]
+        .spacing(10);
+
+        let current_skin = &self.config.display.sprite_dir;
+        let mut skin = row![
+            text("Sprite pack").width(120),
+            pick_list(self.skins.as_slice(), Some(Skin(current_skin.clone())), Message::Skin),
+        ]
+        .spacing(10);
+        if let Some(handle) = self.thumbnails.get(current_skin) {
+            skin = skin.push(image(handle.clone()).width(THUMBNAIL_SIZE).height(THUMBNAIL_SIZE));
+        }
+
+        let deny_titles = self.config.privacy.deny_titles.iter().enumerate().fold(
+            Column::new().spacing(4),
+            |list, (index, title)| {
+                list.push(row![text(title).width(Length::Fill), button("Remove").on_press(Message::RemoveDenyTitle(index))].spacing(10))
+            },
+        );
+        let add_deny_title = row![
+            text_input("Window title contains...", &self.new_deny_title)
+                .on_input(Message::NewDenyTitle)
+                .on_submit(Message::AddDenyTitle),
+            button("Add").on_press(Message::AddDenyTitle),
+        ]
+        .spacing(10);
+
+        let mut save = button("Save");
+        if self.load_error.is_none() {
+            save = save.on_press(Message::Save);
+        }
+
+        let content = column![
+            text("Comments").size(22),
+            provider,
+            api_key,
+            persona,
+            preview,
+            interval("Average every", comments.average_interval_secs, Message::AverageInterval),
+            interval("At least", comments.min_interval_secs, Message::MinInterval),
+            interval("At most", comments.max_interval_secs, Message::MaxInterval),
+            text("Speech").size(22),
+            voice,
+            text("Sprites").size(22),
+            skin,
+            text("Privacy").size(22),
+            text("No screenshots while the focused window's title contains:").size(14),
+            deny_titles,
+            add_deny_title,
+            row![save, text(&self.status).size(14)].spacing(10),
+        ]
+        .spacing(12)
+        .padding(20);
+
+        scrollable(content).into()
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::Dark
+    }
+}
+
+fn thumbnail(dir: &std::path::Path) -> Option<image::Handle> {
+    let frame = tray::sprite_icon(dir)?;
+    let (width, height) = frame.dimensions();
+    Some(image::Handle::from_pixels(width, height, frame.into_raw()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_edit_and_save() {
+        let path = std::env::temp_dir().join(format!("pycatai-settings-{}", std::process::id())).join("config.toml");
+        let mut config = Config::default();
+        config.display.sprite_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites");
+        config.save(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap().replace("[speech]\n", "[speech]\n# my voice settings\n");
+        std::fs::write(&path, text).unwrap();
+        let mut window = SettingsWindow::load(path.clone());
+        assert!(window.thumbnails.contains_key(&config.display.sprite_dir));
+
+        for message in [
+            Message::Provider(Provider::OpenAi),
+            Message::ApiKey("sk-test".to_string()),
+            Message::Persona("grumpy".to_string()),
+            Message::MinInterval(3600.0),
+            Message::NewDenyTitle(" Bank ".to_string()),
+            Message::AddDenyTitle,
+            Message::Save,
+        ] {
+            let _ = window.update(message);
+        }
+
+        let saved = Config::load(&path).unwrap();
+        assert_eq!(saved.comments.provider, Provider::OpenAi);
+        assert_eq!(saved.comments.api_key.as_deref(), Some("sk-test"));
+        assert_eq!(saved.comments.persona, "grumpy");
+        assert_eq!(saved.comments.min_interval_secs, 3600.0);
+        assert!(saved.comments.average_interval_secs >= 3600.0);
+        assert_eq!(saved.privacy.deny_titles, vec!["Bank".to_string()]);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("# my voice settings"));
+
+        // Cấu hình sai thì không ghi đè file
+        window.config.comments.http_timeout_secs = 0.0;
+        let _ = window.update(Message::Save);
+        assert!(window.status.starts_with("Not saved"));
+        assert_eq!(Config::load(&path).unwrap(), saved);
+
+        // File hỏng: báo lỗi và không cho lưu đè bằng cấu hình mặc định
+        std::fs::write(&path, "[display]\ntick_ms = [\n").unwrap();
+        let mut window = SettingsWindow::load(path.clone());
+        assert!(window.status.starts_with("Fix config.toml"));
+        let _ = window.update(Message::Save);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[display]\ntick_ms = [\n");
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+// Tiếng mèo: bộ sprite có thể kèm thư mục sounds/ với file sounds.toml khai
+// báo clip cho từng sự kiện (thức dậy, được vuốt ve, tiếp đất...) hoặc từng
+// animation. Ví dụ:
+//
+//   [events]
+//   wake = "meow.wav"
+//   pet = "purr.ogg"
+//
+//   [animations]
+//   jump_right = "meow.wav"
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::audio::{AudioOutput, Clip};
+use crate::config::{Config, QuietConfig, SoundConfig};
+use crate::timing::Clock;
+
+pub const SOUNDS_DIR: &str = "sounds";
+const MANIFEST_FILE: &str = "sounds.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEvent {
+    // Mèo thức dậy sau khi ngủ, xuất hiện lại sau khi biến mất, hoặc chào khi
+    // chương trình được mở lần nữa
+    Wake,
+    Pet,
+    Jump,
+    Land,
+    Comment,
+}
+
+impl SoundEvent {
+    pub const ALL: [SoundEvent; 5] = [Self::Wake, Self::Pet, Self::Jump, Self::Land, Self::Comment];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wake => "wake",
+            Self::Pet => "pet",
+            Self::Jump => "jump",
+            Self::Land => "land",
+            Self::Comment => "comment",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    events: HashMap<String, String>,
+    animations: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SoundPack {
+    pub events: HashMap<SoundEvent, Clip>,
+    pub animations: HashMap<String, Clip>,
+}
+
+impl SoundPack {
+    // Loads <sprite dir>/sounds/sounds.toml; a pack without one is silent.
+    // Reports every unknown event and unreadable clip at once.
+    pub fn load(sprite_dir: &Path) -> Result<Self> {
+        let dir = sprite_dir.join(SOUNDS_DIR);
+        let manifest_path = dir.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&manifest_path)?;
+        let manifest: Manifest =
+            toml::from_str(&text).map_err(|e| anyhow!("{}: {}", manifest_path.display(), e))?;
+
+        let mut pack = Self::default();
+        let mut problems = Vec::new();
+        let load = |key: &str, file: &str| {
+            Clip::load(&dir.join(file)).map_err(|e| format!("{}: cannot load {}: {}", key, file, e))
+        };
+
+        for (name, file) in &manifest.events {
+            let Some(event) = SoundEvent::ALL.into_iter().find(|event| event.name() == name) else {
+                problems.push(format!("{}: unknown sound event", name));
+                continue;
+            };
+            match load(name, file) {
+                Ok(clip) => {
+                    pack.events.insert(event, clip);
+                }
+                Err(problem) => problems.push(problem),
+            }
+        }
+        for (name, file) in &manifest.animations {
+            match load(name, file) {
+                Ok(clip) => {
+                    pack.animations.insert(name.clone(), clip);
+                }
+                Err(problem) => problems.push(problem),
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(pack)
+        } else {
+            problems.sort();
+            Err(anyhow!("{}", problems.join("\n")))
+        }
+    }
+
+    // Mô tả ngắn từng clip, dùng cho validate-sprites
+    pub fn summary(&self) -> Vec<String> {
+        let describe = |kind: &str, name: &str, clip: &Clip| {
+            format!("{} {}: {} ({:.2}s)", kind, name, clip.name, clip.duration().as_secs_f32())
+        };
+        let mut lines: Vec<String> = self
+            .events
+            .iter()
+            .map(|(event, clip)| describe("sound", event.name(), clip))
+            .chain(self.animations.iter().map(|(name, clip)| describe("animation sound", name, clip)))
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+// Phát clip theo cấu hình: tắt tiếng, âm lượng và khung giờ yên lặng
+pub struct SoundPlayer {
+    pack: SoundPack,
+    output: Box<dyn AudioOutput>,
+    config: SoundConfig,
+    quiet: QuietConfig,
+    clock: Arc<dyn Clock>,
+    // Tắt tạm thời, ví dụ khi có cửa sổ toàn màn hình
+    suppressed: bool,
+    // Tắt tiếng từ khay hệ thống
+    muted: bool,
+}
+
+impl SoundPlayer {
+    pub fn new(pack: SoundPack, output: Box<dyn AudioOutput>, config: &Config, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            pack,
+            output,
+            config: config.sounds.clone(),
+            quiet: config.quiet.clone(),
+            clock,
+            suppressed: false,
+            muted: false,
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &Config) {
+        self.config = config.sounds.clone();
+        self.quiet = config.quiet.clone();
+    }
+
+    pub fn set_pack(&mut self, pack: SoundPack) {
+        self.pack = pack;
+    }
+
+    pub fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+            || self.suppressed
+            || !self.config.enabled
+            || self.config.volume <= 0.0
+            || self.quiet.is_quiet(self.clock.local_time().time())
+    }
+
+    pub fn play(&mut self, event: SoundEvent) {
+        if self.is_muted() {
+            return;
+        }
+        if let Some(clip) = self.pack.events.get(&event) {
+            play(&mut *self.output, clip, self.config.volume);
+        }
+    }
+
+    pub fn play_animation(&mut self, name: &str) {
+        if self.is_muted() {
+            return;
+        }
+        if let Some(clip) = self.pack.animations.get(name) {
+            play(&mut *self.output, clip, self.config.volume);
+        }
+    }
+}
+
+fn play(output: &mut dyn AudioOutput, clip: &Clip, volume: f32) {
+    info!("Playing sound {}", clip.name);
+    if let Err(e) = output.play(clip, volume) {
+        error!("Failed to play sound {}: {:#}", clip.name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::RecordingOutput;
+    use crate::timing::{FakeClock, TimeRange};
+    use std::time::Duration;
+
+    #[test]
+    fn test_bundled_sounds_are_valid() {
+        let pack = SoundPack::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("sprites")).unwrap();
+        for event in [SoundEvent::Wake, SoundEvent::Pet, SoundEvent::Land] {
+            assert!(pack.events.contains_key(&event), "{}", event.name());
+        }
+    }
+
+    #[test]
+    fn test_invalid_manifest_reports_problems() {
+        let dir = std::env::temp_dir().join(format!("pycatai-sounds-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(SOUNDS_DIR)).unwrap();
+        assert!(SoundPack::load(&dir).unwrap().events.is_empty());
+
+        std::fs::write(
+            dir.join(SOUNDS_DIR).join(MANIFEST_FILE),
+            "[events]\nsneeze = \"a.wav\"\nland = \"missing.wav\"\n",
+        )
+        .unwrap();
+        let error = SoundPack::load(&dir).unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.contains("sneeze: unknown sound event"));
+        assert!(error.contains("land: cannot load missing.wav"));
+    }
+
+    #[test]
+    fn test_mute_volume_and_quiet_hours() {
+        let clip = Clip { name: "thud".to_string(), channels: 1, sample_rate: 8000, samples: vec![0] };
+        let pack = SoundPack { events: HashMap::from([(SoundEvent::Land, clip)]), animations: HashMap::new() };
+        let output = RecordingOutput::default();
+        // FakeClock bắt đầu lúc 12:00
+        let clock = Arc::new(FakeClock::new());
+        let mut config = Config::default();
+        config.sounds.volume = 0.5;
+        let mut player = SoundPlayer::new(pack, Box::new(output.clone()), &config, clock.clone());
+
+        player.play(SoundEvent::Land);
+        player.play(SoundEvent::Pet);
+        assert_eq!(output.played(), vec![("thud".to_string(), 0.5)]);
+
+        config.quiet.hours = vec![TimeRange::try_from("12:30-13:00".to_string()).unwrap()];
+        player.apply_config(&config);
+        player.play(SoundEvent::Land);
+        clock.advance(Duration::from_secs(40 * 60));
+        player.play(SoundEvent::Land);
+        assert_eq!(output.played().len(), 2);
+        clock.advance(Duration::from_secs(20 * 60));
+        player.play(SoundEvent::Land);
+        assert_eq!(output.played().len(), 3);
+
+        config.sounds.enabled = false;
+        player.apply_config(&config);
+        player.play(SoundEvent::Land);
+        assert_eq!(output.played().len(), 3);
+    }
+}
@@ -0,0 +1,383 @@
+// Đọc to bình luận: các backend giọng nói và hàng đợi câu nói
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tts::Tts;
+
+use crate::config::{Config, QueuePolicy, SpeechBackendKind, SpeechConfig};
+use crate::metrics;
+
+pub trait SpeechBackend: Send {
+    fn speak(&mut self, text: &str) -> Result<()>;
+    fn stop(&mut self) -> Result<()>;
+    // Whether the last utterance is still being spoken
+    fn is_speaking(&mut self) -> bool;
+    fn set_voice(&mut self, config: &SpeechConfig) -> Result<()>;
+}
+
+// Giọng của hệ điều hành qua crate tts (SAPI/WinRT, speech-dispatcher...)
+pub struct SystemSpeech {
+    tts: Tts,
+    // Used when the platform cannot tell whether it is still speaking
+    estimated_end: Option<Instant>,
+}
+
+impl SystemSpeech {
+    pub fn new() -> Result<Self> {
+        let mut tts = Tts::default()?;
+        // Chọn voice tiếng Anh đầu tiên tìm thấy
+        if let Ok(voices) = tts.voices() {
+            if let Some(voice) = voices.iter().find(|v| v.language().starts_with("en")) {
+                if let Err(e) = tts.set_voice(voice) {
+                    error!("Failed to set English voice: {}", e);
+                }
+            }
+        }
+        Ok(Self { tts, estimated_end: None })
+    }
+}
+
+impl SpeechBackend for SystemSpeech {
+    fn speak(&mut self, text: &str) -> Result<()> {
+        self.tts.speak(text, false)?;
+        self.estimated_end = Some(Instant::now() + speech_duration(text));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.estimated_end = None;
+        if self.tts.supported_features().stop {
+            self.tts.stop()?;
+        }
+        Ok(())
+    }
+
+    fn is_speaking(&mut self) -> bool {
+        if self.tts.supported_features().is_speaking {
+            self.tts.is_speaking().unwrap_or(false)
+        } else {
+            self.estimated_end.is_some_and(|end| Instant::now() < end)
+        }
+    }
+
+    fn set_voice(&mut self, config: &SpeechConfig) -> Result<()> {
+        let features = self.tts.supported_features();
+        let tts = &mut self.tts;
+        if features.rate {
+            let rate = scale(config.rate, RATE_RANGE, (tts.min_rate(), tts.normal_rate(), tts.max_rate()));
+            tts.set_rate(rate)?;
+        }
+        if features.pitch {
+            let pitch = scale(config.pitch, PITCH_RANGE, (tts.min_pitch(), tts.normal_pitch(), tts.max_pitch()));
+            tts.set_pitch(pitch)?;
+        }
+        if features.volume {
+            let (min, max) = (tts.min_volume(), tts.max_volume());
+            tts.set_volume(min + (max - min) * config.volume)?;
+        }
+        if let (true, Some(name)) = (features.voice, &config.voice) {
+            let voice = tts
+                .voices()?
+                .into_iter()
+                .find(|voice| voice.name() == *name)
+                .ok_or_else(|| anyhow!("No voice named '{}'", name))?;
+            tts.set_voice(&voice)?;
+        }
+        Ok(())
+    }
+}
+
+// Không phát ra tiếng, dùng khi tắt TTS hoặc chạy không màn hình
+pub struct NullSpeech;
+
+impl SpeechBackend for NullSpeech {
+    fn speak(&mut self, text: &str) -> Result<()> {
+        info!("Not speaking (speech disabled): {}", text);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_speaking(&mut self) -> bool {
+        false
+    }
+
+    fn set_voice(&mut self, _config: &SpeechConfig) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Ghi lại các lệnh cho test; câu nói kéo dài tới khi test gọi finish()
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingSpeech {
+    state: std::sync::Arc<std::sync::Mutex<(Vec<String>, bool)>>,
+}
+
+#[cfg(test)]
+impl RecordingSpeech {
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().0.clone()
+    }
+
+    pub fn finish(&self) {
+        self.state.lock().unwrap().1 = false;
+    }
+}
+
+#[cfg(test)]
+impl SpeechBackend for RecordingSpeech {
+    fn speak(&mut self, text: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.0.push(format!("speak {}", text));
+        state.1 = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.0.push("stop".to_string());
+        state.1 = false;
+        Ok(())
+    }
+
+    fn is_speaking(&mut self) -> bool {
+        self.state.lock().unwrap().1
+    }
+
+    fn set_voice(&mut self, config: &SpeechConfig) -> Result<()> {
+        self.state.lock().unwrap().0.push(format!(
+            "voice rate={} pitch={} volume={}",
+            config.rate, config.pitch, config.volume
+        ));
+        Ok(())
+    }
+}
+
+// Backend theo cấu hình; lỗi khởi tạo TTS thì im lặng thay vì dừng chương trình
+pub fn backend(config: &Config) -> Box<dyn SpeechBackend> {
+    if !config.comments.tts {
+        info!("TTS disabled");
+        return Box::new(NullSpeech);
+    }
+    let speech: Result<Box<dyn SpeechBackend>> = match config.speech.backend {
+        SpeechBackendKind::System => SystemSpeech::new().map(|speech| Box::new(speech) as _),
+        #[cfg(feature = "piper")]
+        SpeechBackendKind::Piper => crate::piper::PiperSpeech::new(&config.speech).map(|speech| Box::new(speech) as _),
+        #[cfg(not(feature = "piper"))]
+        SpeechBackendKind::Piper => Err(anyhow!("this build has no Piper support")),
+    };
+    speech.unwrap_or_else(|e| {
+        error!("Failed to initialize TTS: {}, comments will not be spoken", e);
+        metrics::increment(metrics::TTS_FAILURES);
+        Box::new(NullSpeech)
+    })
+}
+
+// Các thiết lập mà đổi thì phải tạo lại backend
+type BackendKey = (bool, SpeechBackendKind, PathBuf, Option<PathBuf>, Option<u32>);
+
+fn backend_key(config: &Config) -> BackendKey {
+    let speech = &config.speech;
+    (
+        config.comments.tts,
+        speech.backend,
+        speech.piper_executable.clone(),
+        speech.piper_model.clone(),
+        speech.piper_speaker,
+    )
+}
+
+// Tên và ngôn ngữ của các giọng hệ điều hành
+pub fn list_voices() -> Result<Vec<(String, String)>> {
+    let tts = Tts::default().map_err(|e| anyhow!("Failed to initialize TTS: {}", e))?;
+    Ok(tts
+        .voices()?
+        .iter()
+        .map(|voice| (voice.name(), voice.language().to_string()))
+        .collect())
+}
+
+// Hàng đợi câu nói trước một backend
+pub struct Speaker {
+    backend: Box<dyn SpeechBackend>,
+    backend_key: BackendKey,
+    policy: QueuePolicy,
+    queue: VecDeque<String>,
+    current: Option<String>,
+    muted: bool,
+}
+
+impl Speaker {
+    pub fn new(backend: Box<dyn SpeechBackend>, config: &Config) -> Self {
+        let mut speaker = Self {
+            backend,
+            backend_key: backend_key(config),
+            policy: config.speech.queue,
+            queue: VecDeque::new(),
+            current: None,
+            muted: false,
+        };
+        speaker.set_voice(&config.speech);
+        speaker
+    }
+
+    pub fn apply_config(&mut self, config: &Config) {
+        if backend_key(config) != self.backend_key {
+            self.stop();
+            self.backend = backend(config);
+            self.backend_key = backend_key(config);
+        }
+        self.policy = config.speech.queue;
+        self.set_voice(&config.speech);
+    }
+
+    fn set_voice(&mut self, config: &SpeechConfig) {
+        if let Err(e) = self.backend.set_voice(config) {
+            error!("Failed to apply voice settings: {}", e);
+        }
+    }
+
+    // Tắt tiếng thì bỏ luôn câu đang nói và các câu đang chờ
+    pub fn set_muted(&mut self, muted: bool) {
+        if muted {
+            self.stop();
+        }
+        self.muted = muted;
+    }
+
+    // Nói ngay (ngắt câu đang nói) hoặc xếp hàng, tuỳ cấu hình
+    pub fn say(&mut self, text: &str) {
+        if self.muted {
+            return;
+        }
+        match self.policy {
+            QueuePolicy::Interrupt => {
+                self.stop();
+                self.start(text.to_string());
+            }
+            QueuePolicy::Enqueue if self.current.is_some() => self.queue.push_back(text.to_string()),
+            QueuePolicy::Enqueue => self.start(text.to_string()),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.queue.clear();
+        if self.current.take().is_some() {
+            if let Err(e) = self.backend.stop() {
+                error!("Failed to stop speech: {}", e);
+            }
+        }
+    }
+
+    fn start(&mut self, text: String) {
+        info!("Speaking comment: {}", text);
+        match self.backend.speak(&text) {
+            Ok(()) => self.current = Some(text),
+            Err(e) => {
+                error!("TTS failed: {}", e);
+                metrics::increment(metrics::TTS_FAILURES);
+            }
+        }
+    }
+
+    pub fn is_speaking(&self) -> bool {
+        self.current.is_some()
+    }
+
+    // Call every tick: `on_finished` gets each utterance that has been fully
+    // spoken, then the next queued one starts
+    pub fn poll(&mut self, mut on_finished: impl FnMut(&str)) {
+        if self.current.is_none() || self.backend.is_speaking() {
+            return;
+        }
+        if let Some(text) = self.current.take() {
+            on_finished(&text);
+        }
+        if let Some(next) = self.queue.pop_front() {
+            self.start(next);
+        }
+    }
+}
+
+// Allowed rate and pitch multipliers; 1.0 is the voice's normal value
+pub const RATE_RANGE: (f32, f32) = (0.25, 4.0);
+pub const PITCH_RANGE: (f32, f32) = (0.5, 2.0);
+
+// Map a multiplier in `range` onto a backend's (min, normal, max), which
+// differ per platform (e.g. -100..100 or 0.1..10)
+fn scale(value: f32, range: (f32, f32), (min, normal, max): (f32, f32, f32)) -> f32 {
+    let (low, high) = range;
+    let value = value.clamp(low, high);
+    if value >= 1.0 {
+        normal + (max - normal) * (value - 1.0) / (high - 1.0)
+    } else {
+        normal - (normal - min) * (1.0 - value) / (1.0 - low)
+    }
+}
+
+// Ước lượng thời gian TTS đọc xong (~150 từ/phút)
+fn speech_duration(text: &str) -> Duration {
+    Duration::from_millis(400) * text.split_whitespace().count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speaker(policy: QueuePolicy) -> (Speaker, RecordingSpeech) {
+        let recording = RecordingSpeech::default();
+        let mut config = Config::default();
+        config.speech.queue = policy;
+        config.speech.rate = 1.5;
+        (Speaker::new(Box::new(recording.clone()), &config), recording)
+    }
+
+    #[test]
+    fn test_enqueue_waits_for_each_utterance() {
+        let (mut speaker, recording) = speaker(QueuePolicy::Enqueue);
+        speaker.say("one");
+        speaker.say("two");
+        assert_eq!(recording.calls(), vec!["voice rate=1.5 pitch=1 volume=1", "speak one"]);
+
+        let mut finished = Vec::new();
+        speaker.poll(|text| finished.push(text.to_string()));
+        assert!(finished.is_empty());
+
+        recording.finish();
+        speaker.poll(|text| finished.push(text.to_string()));
+        assert_eq!(finished, vec!["one"]);
+        assert_eq!(recording.calls().last().unwrap(), "speak two");
+
+        recording.finish();
+        speaker.poll(|text| finished.push(text.to_string()));
+        assert_eq!(finished, vec!["one", "two"]);
+        assert!(!speaker.is_speaking());
+    }
+
+    #[test]
+    fn test_interrupt_stops_current_utterance() {
+        let (mut speaker, recording) = speaker(QueuePolicy::Interrupt);
+        speaker.say("one");
+        speaker.say("two");
+        assert_eq!(recording.calls()[1..], ["speak one", "stop", "speak two"]);
+
+        // Câu bị ngắt không được báo là đã đọc xong
+        let mut finished = Vec::new();
+        recording.finish();
+        speaker.poll(|text| finished.push(text.to_string()));
+        assert_eq!(finished, vec!["two"]);
+    }
+
+    #[test]
+    fn test_scale_to_platform_range() {
+        assert_eq!(scale(1.0, RATE_RANGE, (-100.0, 0.0, 100.0)), 0.0);
+        assert_eq!(scale(4.0, RATE_RANGE, (-100.0, 0.0, 100.0)), 100.0);
+        assert!((scale(0.25, RATE_RANGE, (0.1, 1.0, 10.0)) - 0.1).abs() < 1e-5);
+        assert_eq!(scale(2.0, PITCH_RANGE, (0.5, 1.0, 2.0)), 2.0);
+    }
+}
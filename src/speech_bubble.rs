@@ -0,0 +1,448 @@
+// Khung chat (speech bubble) của mèo: ngắt dòng, chọn vị trí quanh mèo,
+// hiệu ứng hiện từng chữ và mờ dần khi hết thời gian hiển thị.
+use iced::widget::canvas::{self, path::Builder, Frame, Geometry, Path, Program, Text};
+use iced::{mouse, Color, Element, Length, Point, Rectangle, Renderer, Size, Theme};
+use std::time::{Duration, Instant};
+
+use crate::window_handler::WindowRect;
+
+pub const FONT_SIZE: f32 = 16.0;
+pub const LINE_HEIGHT: f32 = 20.0;
+pub const PADDING: f32 = 10.0;
+pub const CORNER_RADIUS: f32 = 10.0;
+pub const TAIL_SIZE: i32 = 12;
+pub const MAX_TEXT_WIDTH: f32 = 200.0;
+pub const MAX_LINES: usize = 8;
+pub const MAX_BUBBLE_WIDTH: i32 = (MAX_TEXT_WIDTH + 2.0 * PADDING) as i32;
+pub const MAX_BUBBLE_HEIGHT: i32 = (MAX_LINES as f32 * LINE_HEIGHT + 2.0 * PADDING) as i32;
+
+// Average glyph advance used for wrapping; deliberately a little wide so
+// the real font never overflows the bubble
+const CHAR_WIDTH: f32 = FONT_SIZE * 0.55;
+
+// Typewriter speed and fade-out length
+const TIME_PER_CHAR: Duration = Duration::from_millis(32);
+const FADE_DURATION: Duration = Duration::from_millis(480);
+
+// Màu nền và màu chữ khi khung chat hiện rõ hoàn toàn
+const BACKGROUND: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 0.95 };
+const FOREGROUND: Color = Color { r: 0.1, g: 0.1, b: 0.1, a: 1.0 };
+
+fn faded(color: Color, opacity: f32) -> Color {
+    Color { a: color.a * opacity, ..color }
+}
+
+pub fn text_width(text: &str) -> f32 {
+    text.chars().count() as f32 * CHAR_WIDTH
+}
+
+// Greedy word wrap to `max_width`. Words longer than a line are split, and
+// text beyond MAX_LINES is cut with an ellipsis.
+pub fn wrap(text: &str, max_width: f32) -> Vec<String> {
+    let max_chars = ((max_width / CHAR_WIDTH) as usize).max(1);
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        // Hard-break words that do not fit on a line of their own
+        while word.len() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let rest = word.split_off(max_chars);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+
+        let word: String = word.into_iter().collect();
+        if line.is_empty() {
+            line = word;
+        } else if line.chars().count() + 1 + word.chars().count() <= max_chars {
+            line.push(' ');
+            line.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut line, word));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES);
+        let last = lines.last_mut().unwrap();
+        while last.chars().count() + 1 > max_chars {
+            last.pop();
+        }
+        last.push('…');
+    }
+
+    lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Above,
+    Left,
+    Right,
+}
+
+// Vị trí của mèo và khung chat trong cửa sổ (toạ độ cửa sổ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BubbleLayout {
+    pub placement: Placement,
+    // Screen position of the window's top-left corner
+    pub window_origin: (i32, i32),
+    pub cat: WindowRect,
+    // Rounded body of the bubble
+    pub body: WindowRect,
+    // Area drawn by the bubble widget: body plus tail
+    pub canvas: WindowRect,
+    pub tail_tip: (i32, i32),
+}
+
+// Place a bubble of `size` around `cat` (screen coordinates) so it stays on
+// `screen`: above the cat when there is room, otherwise beside it. With no
+// bubble the cat sits at the bottom-left of the window.
+pub fn layout(cat: WindowRect, screen: WindowRect, size: (i32, i32), window_height: i32) -> BubbleLayout {
+    let (width, height) = size;
+    let center = (cat.left + cat.right) / 2;
+    let room_above = cat.top - TAIL_SIZE - height >= screen.top;
+
+    let (placement, body) = if height == 0 || room_above {
+        let x = (center - width / 2).min(screen.right - width).max(screen.left);
+        let bottom = cat.top - TAIL_SIZE;
+        (Placement::Above, WindowRect::new(x, bottom - height, x + width, bottom))
+    } else {
+        let y = cat.top.max(screen.top);
+        if cat.right + TAIL_SIZE + width <= screen.right {
+            let left = cat.right + TAIL_SIZE;
+            (Placement::Right, WindowRect::new(left, y, left + width, y + height))
+        } else {
+            let right = cat.left - TAIL_SIZE;
+            (Placement::Left, WindowRect::new(right - width, y, right, y + height))
+        }
+    };
+
+    // Keep the tail clear of the rounded corners
+    let inset = CORNER_RADIUS as i32 + TAIL_SIZE / 2;
+    let (canvas, tail_tip) = match placement {
+        Placement::Above => (
+            WindowRect::new(body.left, body.top, body.right, cat.top),
+            (center.min(body.right - inset).max(body.left + inset), cat.top),
+        ),
+        Placement::Right => (
+            WindowRect::new(cat.right, body.top, body.right, body.bottom),
+            (cat.right, (cat.top + cat.height() / 3).min(body.bottom - inset).max(body.top + inset)),
+        ),
+        Placement::Left => (
+            WindowRect::new(body.left, body.top, cat.left, body.bottom),
+            (cat.left, (cat.top + cat.height() / 3).min(body.bottom - inset).max(body.top + inset)),
+        ),
+    };
+
+    let origin_x = cat.left.min(canvas.left);
+    let origin_y = match placement {
+        Placement::Above => cat.bottom - window_height,
+        Placement::Left | Placement::Right => cat.top.min(canvas.top),
+    };
+    let local = |r: WindowRect| WindowRect::new(r.left - origin_x, r.top - origin_y, r.right - origin_x, r.bottom - origin_y);
+
+    BubbleLayout {
+        placement,
+        window_origin: (origin_x, origin_y),
+        cat: local(cat),
+        body: local(body),
+        canvas: local(canvas),
+        tail_tip: (tail_tip.0 - origin_x, tail_tip.1 - origin_y),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeechBubble {
+    lines: Vec<String>,
+    shown_at: Instant,
+    fade_from: Option<Instant>,
+}
+
+impl SpeechBubble {
+    pub fn new(text: &str, now: Instant) -> Self {
+        Self {
+            lines: wrap(text, MAX_TEXT_WIDTH),
+            shown_at: now,
+            fade_from: None,
+        }
+    }
+
+    pub fn start_fade(&mut self, now: Instant) {
+        self.fade_from.get_or_insert(now);
+    }
+
+    pub fn is_faded(&self, now: Instant) -> bool {
+        self.fade_from
+            .is_some_and(|from| now.saturating_duration_since(from) >= FADE_DURATION)
+    }
+
+    pub fn opacity(&self, now: Instant) -> f32 {
+        match self.fade_from {
+            Some(from) => {
+                let faded = now.saturating_duration_since(from).as_secs_f32() / FADE_DURATION.as_secs_f32();
+                1.0 - faded.min(1.0)
+            }
+            None => 1.0,
+        }
+    }
+
+    // Size of the body for the full text, so the bubble does not grow while typing
+    pub fn size(&self) -> (i32, i32) {
+        let text_width = self.lines.iter().map(|l| text_width(l)).fold(0.0, f32::max);
+        (
+            (text_width + 2.0 * PADDING).ceil() as i32,
+            (self.lines.len() as f32 * LINE_HEIGHT + 2.0 * PADDING).ceil() as i32,
+        )
+    }
+
+    // Lines revealed so far by the typewriter effect
+    pub fn visible_lines(&self, now: Instant) -> Vec<String> {
+        let age = now.saturating_duration_since(self.shown_at);
+        let mut remaining = (age.as_millis() / TIME_PER_CHAR.as_millis()) as usize;
+        let mut visible = Vec::with_capacity(self.lines.len());
+
+        for line in &self.lines {
+            if remaining == 0 {
+                break;
+            }
+            let count = line.chars().count();
+            visible.push(line.chars().take(remaining).collect());
+            remaining = remaining.saturating_sub(count);
+        }
+
+        visible
+    }
+
+    pub fn view<'a, Message: 'a>(&self, layout: &BubbleLayout, now: Instant) -> Element<'a, Message> {
+        let canvas = layout.canvas;
+        let offset = |r: WindowRect| WindowRect::new(r.left - canvas.left, r.top - canvas.top, r.right - canvas.left, r.bottom - canvas.top);
+
+        canvas::Canvas::new(BubbleShape {
+            lines: self.visible_lines(now),
+            placement: layout.placement,
+            body: offset(layout.body),
+            tail_tip: (layout.tail_tip.0 - canvas.left, layout.tail_tip.1 - canvas.top),
+            opacity: self.opacity(now),
+        })
+        .width(Length::Fixed(canvas.width() as f32))
+        .height(Length::Fixed(canvas.height() as f32))
+        .into()
+    }
+
+    // Vẽ khung chat vào ảnh cửa sổ giống như view(). Chữ được vẽ thành các ô
+    // đặc theo CHAR_WIDTH vì glyph thật phụ thuộc font của hệ thống.
+    #[cfg(test)]
+    pub fn render_into(&self, image: &mut image::RgbaImage, layout: &BubbleLayout, now: Instant) {
+        let opacity = self.opacity(now);
+        let background = faded(BACKGROUND, opacity);
+        let foreground = faded(FOREGROUND, opacity);
+
+        // Body and tail are one shape in the canvas, so they must not blend twice
+        let mut shape = image::RgbaImage::new(image.width(), image.height());
+        crate::render::fill_rounded_rect(&mut shape, layout.body, CORNER_RADIUS, Color::WHITE);
+        let tail = tail_points(layout.placement, layout.body, layout.tail_tip).map(|(x, y)| (x as f32, y as f32));
+        crate::render::fill_triangle(&mut shape, tail, Color::WHITE);
+        for (x, y, pixel) in shape.enumerate_pixels() {
+            if pixel.0[3] > 0 {
+                crate::render::blend(image, x as i32, y as i32, background);
+            }
+        }
+
+        for (index, line) in self.visible_lines(now).iter().enumerate() {
+            let top = layout.body.top as f32 + PADDING + index as f32 * LINE_HEIGHT + FONT_SIZE * 0.25;
+            for (column, c) in line.chars().enumerate() {
+                if c.is_whitespace() {
+                    continue;
+                }
+                let left = layout.body.left as f32 + PADDING + column as f32 * CHAR_WIDTH;
+                let glyph = WindowRect::new(
+                    left.round() as i32 + 1,
+                    top.round() as i32,
+                    (left + CHAR_WIDTH).round() as i32 - 1,
+                    (top + FONT_SIZE * 0.6).round() as i32,
+                );
+                crate::render::fill_rect(image, glyph, foreground);
+            }
+        }
+    }
+}
+
+// Hình dạng khung chat được vẽ bằng canvas (toạ độ trong canvas)
+#[derive(Debug, Clone)]
+struct BubbleShape {
+    lines: Vec<String>,
+    placement: Placement,
+    body: WindowRect,
+    tail_tip: (i32, i32),
+    opacity: f32,
+}
+
+impl<Message> Program<Message> for BubbleShape {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let background = faded(BACKGROUND, self.opacity);
+        let foreground = faded(FOREGROUND, self.opacity);
+
+        frame.fill(&body_path(self.body), background);
+        frame.fill(&tail_path(self.placement, self.body, self.tail_tip), background);
+
+        for (index, line) in self.lines.iter().enumerate() {
+            frame.fill_text(Text {
+                content: line.clone(),
+                position: Point::new(
+                    self.body.left as f32 + PADDING,
+                    self.body.top as f32 + PADDING + index as f32 * LINE_HEIGHT,
+                ),
+                color: foreground,
+                size: FONT_SIZE,
+                ..Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+fn body_path(body: WindowRect) -> Path {
+    let (x, y) = (body.left as f32, body.top as f32);
+    let size = Size::new(body.width() as f32, body.height() as f32);
+    let r = CORNER_RADIUS.min(size.width / 2.0).min(size.height / 2.0);
+
+    let mut builder = Builder::new();
+    builder.move_to(Point::new(x + r, y));
+    builder.arc_to(Point::new(x + size.width, y), Point::new(x + size.width, y + size.height), r);
+    builder.arc_to(Point::new(x + size.width, y + size.height), Point::new(x, y + size.height), r);
+    builder.arc_to(Point::new(x, y + size.height), Point::new(x, y), r);
+    builder.arc_to(Point::new(x, y), Point::new(x + size.width, y), r);
+    builder.close();
+    builder.build()
+}
+
+// Tam giác nối khung chat với mèo
+pub fn tail_points(placement: Placement, body: WindowRect, tip: (i32, i32)) -> [(i32, i32); 3] {
+    let half = TAIL_SIZE / 2;
+    match placement {
+        Placement::Above => [(tip.0 - half, body.bottom), (tip.0 + half, body.bottom), tip],
+        Placement::Right => [(body.left, tip.1 - half), (body.left, tip.1 + half), tip],
+        Placement::Left => [(body.right, tip.1 - half), (body.right, tip.1 + half), tip],
+    }
+}
+
+fn tail_path(placement: Placement, body: WindowRect, tip: (i32, i32)) -> Path {
+    let [a, b, c] = tail_points(placement, body, tip);
+    Path::new(|builder| {
+        // Overlap the body by a pixel so no seam shows between the two fills
+        let overlap = match placement {
+            Placement::Above => (0.0, -1.0),
+            Placement::Right => (1.0, 0.0),
+            Placement::Left => (-1.0, 0.0),
+        };
+        builder.move_to(Point::new(a.0 as f32 + overlap.0, a.1 as f32 + overlap.1));
+        builder.line_to(Point::new(b.0 as f32 + overlap.0, b.1 as f32 + overlap.1));
+        builder.line_to(Point::new(c.0 as f32, c.1 as f32));
+        builder.close();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: WindowRect = WindowRect { left: 0, top: 0, right: 1280, bottom: 720 };
+    const WINDOW_HEIGHT: i32 = 300;
+
+    #[test]
+    fn test_wrap_respects_width() {
+        let lines = wrap("Meow! What are you doing? That looks interesting!", 120.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| text_width(l) <= 120.0));
+        assert_eq!(lines.join(" "), "Meow! What are you doing? That looks interesting!");
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_words_and_truncates() {
+        let lines = wrap("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", 88.0);
+        assert!(lines.iter().all(|l| l.chars().count() <= 10));
+
+        let long = "word ".repeat(200);
+        let lines = wrap(&long, MAX_TEXT_WIDTH);
+        assert_eq!(lines.len(), MAX_LINES);
+        assert!(lines.last().unwrap().ends_with('…'));
+    }
+
+    #[test]
+    fn test_layout_above_when_room() {
+        let cat = WindowRect::new(500, 600, 600, 700);
+        let layout = layout(cat, SCREEN, (150, 60), WINDOW_HEIGHT);
+
+        assert_eq!(layout.placement, Placement::Above);
+        // Mèo nằm ở đáy cửa sổ, khung chat ngay phía trên, đuôi chỉ vào đầu mèo
+        assert_eq!(layout.cat.bottom, WINDOW_HEIGHT);
+        assert_eq!(layout.body.bottom + TAIL_SIZE, layout.cat.top);
+        assert_eq!(layout.tail_tip, ((layout.cat.left + layout.cat.right) / 2, layout.cat.top));
+        assert_eq!(layout.window_origin.0 + layout.cat.left, cat.left);
+        assert_eq!(layout.window_origin.1 + layout.cat.top, cat.top);
+    }
+
+    #[test]
+    fn test_layout_clamped_to_screen_edge() {
+        let cat = WindowRect::new(1200, 600, 1300, 700);
+        let layout = layout(cat, SCREEN, (200, 60), WINDOW_HEIGHT);
+
+        assert_eq!(layout.placement, Placement::Above);
+        assert_eq!(layout.window_origin.0 + layout.body.right, SCREEN.right);
+        assert!(layout.tail_tip.0 <= layout.body.right - CORNER_RADIUS as i32);
+    }
+
+    #[test]
+    fn test_layout_beside_near_top() {
+        // Không đủ chỗ phía trên: khung chat nằm bên phải
+        let cat = WindowRect::new(300, 20, 400, 120);
+        let right = layout(cat, SCREEN, (150, 80), WINDOW_HEIGHT);
+        assert_eq!(right.placement, Placement::Right);
+        assert_eq!(right.body.left, right.cat.right + TAIL_SIZE);
+        assert_eq!(right.tail_tip.0, right.cat.right);
+
+        // Sát mép phải màn hình: khung chat nằm bên trái
+        let cat = WindowRect::new(1150, 20, 1250, 120);
+        let left = layout(cat, SCREEN, (150, 80), WINDOW_HEIGHT);
+        assert_eq!(left.placement, Placement::Left);
+        assert_eq!(left.body.right + TAIL_SIZE, left.cat.left);
+        assert_eq!(left.window_origin.0 + left.cat.left, cat.left);
+    }
+
+    #[test]
+    fn test_typewriter_and_fade() {
+        let start = Instant::now();
+        let mut bubble = SpeechBubble::new("Hello there", start);
+        assert!(bubble.visible_lines(start).is_empty());
+
+        let now = start + TIME_PER_CHAR * 5;
+        assert_eq!(bubble.visible_lines(now), vec!["Hello".to_string()]);
+
+        bubble.start_fade(now);
+        assert_eq!(bubble.opacity(now), 1.0);
+        let half = bubble.opacity(now + FADE_DURATION / 2);
+        assert!(half > 0.4 && half < 0.6);
+        assert!(!bubble.is_faded(now + FADE_DURATION / 2));
+        assert!(bubble.is_faded(now + FADE_DURATION));
+    }
+}
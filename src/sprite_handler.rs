@@ -1,254 +1,870 @@
 use anyhow::{Result, anyhow};
 use iced::{
-    widget::{Text, Column, Image, Container},
-    Element, Length,
+    widget::{image::Handle, Column, Image, Container, Row, Text},
+    Element, Font, Length,
 };
 use log::{info, error};
-use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
 use rand::Rng;
+use chrono::NaiveDateTime;
+use iced::futures::channel::oneshot;
+use iced::futures::future::{self, AbortHandle};
+use iced::futures::{Future, FutureExt};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::comment_generator::Commenter;
-use crate::display::Message;
+use crate::audio::PlayerProcess;
+use crate::comment_generator::{CommentJob, Commenter, Mood};
+use crate::config::{Config, MovementConfig, PrivacyConfig, QuietConfig};
+use crate::display::{Message, WINDOW_SIZE};
+use crate::events::{ActivityEvent, ActivityWatcher};
+use crate::metrics;
+use crate::sounds::{SoundEvent, SoundPack, SoundPlayer};
+use crate::speech::{self, Speaker};
+use crate::speech_bubble::{self, BubbleLayout, Placement, SpeechBubble, MAX_BUBBLE_HEIGHT, MAX_BUBBLE_WIDTH};
+use crate::state::PetState;
+use crate::sprite_pack::{SpritePack, DEFAULT_FRAME_DELAY};
+use crate::timing::{self, Clock, CommentSchedule};
+use crate::window_handler::{self, ForegroundWindow, Ledge, WindowInfo, WindowRect};
 
-#[derive(Debug, Clone)]
-enum Direction {
+// Kích thước sprite mèo (pixel)
+pub const CAT_SIZE: i32 = 100;
+
+// Pixels at least this opaque receive clicks in overlay mode
+const HIT_ALPHA_THRESHOLD: u8 = 32;
+
+const TRANSITION_DURATION: Duration = Duration::from_millis(800);
+
+// Longest gap treated as one animation step; longer stalls (sleep, dragged
+// window) would otherwise teleport a falling cat
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+// Hide the chat even if a speech backend never reports being done
+const MAX_SPEECH_WAIT: Duration = Duration::from_secs(120);
+
+// Sự kiện chưa kịp bình luận sau chừng này thì đã cũ, bỏ qua
+const MAX_EVENT_AGE: Duration = Duration::from_secs(30);
+
+// Leo lên cửa sổ
+const LEDGE_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const LEDGE_TOLERANCE: i32 = 8;
+const MIN_LEDGE_WIDTH: i32 = CAT_SIZE / 2;
+const JUMP_REACH: i32 = 150;
+const JUMP_MAX_HEIGHT: i32 = 300;
+const JUMP_ARC: f32 = 60.0;
+const JUMP_DURATION: Duration = Duration::from_millis(640);
+// px/s² và px/s
+const GRAVITY: f32 = 3000.0;
+const MAX_FALL_SPEED: f32 = 1500.0;
+
+// Nhu cầu: thay đổi mỗi giờ, và các mức làm mèo đổi hành vi
+const HUNGER_PER_HOUR: f32 = 0.12;
+const ENERGY_PER_HOUR: f32 = -0.15;
+const SLEEP_ENERGY_PER_HOUR: f32 = 0.6;
+const AFFECTION_PER_HOUR: f32 = -0.05;
+const BOREDOM_PER_HOUR: f32 = 0.25;
+const HUNGRY: f32 = 0.7;
+const SLEEPY: f32 = 0.35;
+const TIRED: f32 = 0.2;
+const RESTED: f32 = 0.9;
+const LONELY: f32 = 0.2;
+const BORED: f32 = 0.7;
+const ENTERTAINED: f32 = 0.3;
+// Energy spent per jump and per running step; play also eases boredom
+const JUMP_EFFORT: f32 = 0.02;
+const RUN_EFFORT: f32 = 0.002;
+// Chạy nhanh gấp mấy lần đi bộ
+const RUN_SPEEDUP: u32 = 4;
+
+// Bảng debug chiếm chỗ của khung chat lớn nhất
+const DEBUG_PANEL_SIZE: (i32, i32) = (MAX_BUBBLE_WIDTH, MAX_BUBBLE_HEIGHT);
+const DEBUG_FONT_SIZE: f32 = 10.0;
+const DEBUG_LINE_CHARS: usize = 34;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
     Left,
     Right,
 }
 
-impl Direction {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Direction::Left => "left".to_string(),
-            Direction::Right => "right".to_string(),
+            Direction::Left => write!(f, "left"),
+            Direction::Right => write!(f, "right"),
         }
     }
-    
+}
+
+impl Direction {
     fn opposite(&self) -> Self {
         match self {
             Direction::Left => Direction::Right,
             Direction::Right => Direction::Left,
         }
     }
+    
+    fn sign(&self) -> i32 {
+        match self {
+            Direction::Left => -1,
+            Direction::Right => 1,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 enum AnimationState {
     Idle,
     Transitioning,
+    Jumping,
+    Falling,
+    Sleeping,
 }
 
+impl AnimationState {
+    fn name(&self) -> &'static str {
+        match self {
+            AnimationState::Idle => "idle",
+            AnimationState::Transitioning => "transitioning",
+            AnimationState::Jumping => "jumping",
+            AnimationState::Falling => "falling",
+            AnimationState::Sleeping => "sleeping",
+        }
+    }
+}
+
+// Trạng thái của mèo tại một thời điểm, dùng cho trace khi chạy mô phỏng
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub state: &'static str,
+    pub animation: String,
+    pub frame: usize,
+    pub x: i32,
+    pub y: i32,
+    pub visible: bool,
+    pub bubble: Option<String>,
+    pub mood: &'static str,
+}
+
+// x là mép trái của sprite, y là vị trí chân mèo (toạ độ màn hình)
 #[derive(Debug, Clone)]
 struct Position {
     x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone)]
+struct Jump {
+    from: (i32, i32),
+    to: (i32, i32),
+    ledge: Ledge,
+    started: Instant,
+}
+
+// Nhu cầu của mèo, mỗi giá trị từ 0.0 tới 1.0. Trôi dần theo thời gian và
+// thay đổi khi người dùng chơi với mèo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Needs {
+    pub hunger: f32,
+    pub energy: f32,
+    pub affection: f32,
+    pub boredom: f32,
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self {
+            hunger: 0.2,
+            energy: 0.8,
+            affection: 0.5,
+            boredom: 0.2,
+        }
+    }
+}
+
+impl Needs {
+    // Ngủ thì hồi năng lượng và không thấy chán
+    pub fn drift(&mut self, elapsed: Duration, sleeping: bool) {
+        let hours = elapsed.as_secs_f32() / 3600.0;
+        self.hunger += HUNGER_PER_HOUR * hours;
+        self.affection += AFFECTION_PER_HOUR * hours;
+        if sleeping {
+            self.energy += SLEEP_ENERGY_PER_HOUR * hours;
+        } else {
+            self.energy += ENERGY_PER_HOUR * hours;
+            self.boredom += BOREDOM_PER_HOUR * hours;
+        }
+        self.clamp();
+    }
+    
+    pub fn pet(&mut self) {
+        self.affection += 0.15;
+        self.boredom -= 0.1;
+        self.clamp();
+    }
+    
+    pub fn feed(&mut self) {
+        self.hunger -= 0.6;
+        self.affection += 0.05;
+        self.clamp();
+    }
+    
+    fn exercise(&mut self, effort: f32) {
+        self.energy -= effort;
+        self.boredom -= effort * 5.0;
+        self.clamp();
+    }
+    
+    fn clamp(&mut self) {
+        for value in [&mut self.hunger, &mut self.energy, &mut self.affection, &mut self.boredom] {
+            *value = value.clamp(0.0, 1.0);
+        }
+    }
+    
+    // Nhu cầu cấp thiết nhất, đói trước rồi tới buồn ngủ, cô đơn, chán
+    pub fn mood(&self) -> Mood {
+        if self.hunger >= HUNGRY {
+            Mood::Hungry
+        } else if self.energy <= SLEEPY {
+            Mood::Sleepy
+        } else if self.affection <= LONELY {
+            Mood::Lonely
+        } else if self.boredom >= BORED {
+            Mood::Bored
+        } else {
+            Mood::Content
+        }
+    }
 }
 
-#[derive(Clone)]
 pub struct SpriteController {
     // Sprite và animation
+    sprite_dir: PathBuf,
     animation_frames: HashMap<String, Vec<image::DynamicImage>>,
+    frame_delays: HashMap<String, Vec<Duration>>,
+    frame_handles: HashMap<String, Vec<Handle>>,
     current_animation: String,
     frame_index: usize,
     max_frame_index: usize,
+    next_frame_at: Instant,
+    sounds: SoundPlayer,
     
     // Vị trí và di chuyển
+    movement: MovementConfig,
     pos: Position,
     direction: Direction,
     animation_state: AnimationState,
     
+    // Nhu cầu: ngủ khi mệt, chạy lung tung khi chán
+    needs: Needs,
+    resting: bool,
+    running: bool,
+    
     // Thời gian và delay
-    last_update: Instant,
-    idle_delay: u32,
-    max_idle_delay: u32,
+    clock: Arc<dyn Clock>,
+    rng: StdRng,
+    last_tick: Instant,
+    next_step_at: Instant,
     
     // Chat và comment
     commenter: Commenter,
+    persona: String,
+    last_comment_at: Option<NaiveDateTime>,
+    // Bình luận đang chờ AI: việc chưa giao cho Display, rồi cách huỷ nó
+    // khi đã chạy
+    comment_job: Option<CommentJob>,
+    comment_abort: Option<AbortHandle>,
+    // Đang thoát: không bắt đầu bình luận hay câu nói mới
+    shutting_down: bool,
+    // Tắt tiếng từ khay hệ thống: không đọc, không kêu
+    muted: bool,
+    speaker: Speaker,
+    comment_schedule: CommentSchedule,
+    next_comment_at: Instant,
     chat_response: Option<String>,
     chat_visible: bool,
-    chat_duration: u32,
-    chat_max_duration: u32,
+    chat_shown_at: Instant,
+    chat_max_duration: Duration,
+    bubble: Option<SpeechBubble>,
+    // Sự kiện trên màn hình chờ mèo rảnh để bình luận
+    activity: ActivityWatcher,
+    pending_event: Option<(ActivityEvent, Instant)>,
     
     // Màn hình và giới hạn
     screen_width: i32,
+    screen_height: i32,
+    
+    // Đi trên mép cửa sổ
+    window_info: Arc<dyn WindowInfo>,
+    ledges: Vec<Ledge>,
+    perch: Option<Ledge>,
+    next_ledge_refresh: Instant,
+    jump: Option<Jump>,
+    fall_speed: f32,
     
     // Chuyển màn hình
     is_transitioning: bool,
-    transition_complete: u32,
+    transition_started: Instant,
     transition_x: i32,
     
     // Biến mất
     is_disappeared: bool,
-    disappear_timer: u32,
-    disappear_duration: u32,
+    reappear_at: Instant,
+    
+    // Không làm phiền
+    quiet: QuietConfig,
+    quiet_reason: Option<&'static str>,
+    privacy: PrivacyConfig,
+    snoozed_until: Option<Instant>,
+    fullscreen: bool,
+    sleep_started: Instant,
+    
+    // Bảng debug cạnh mèo (phím D)
+    debug: bool,
+    // Mừng hoặc thất vọng khi một lệnh chạy xong, ngồi xuống khi hết animation
+    reaction_ends_at: Option<Instant>,
 }
 
 impl SpriteController {
-    pub fn new() -> Self {
+    pub fn new(window_info: Arc<dyn WindowInfo>, clock: Arc<dyn Clock>, config: &Config, seed: Option<u64>) -> Self {
         info!("Initializing SpriteController");
         
-        // Get screen dimensions
-        let screen_width = 1920; // Default value, will update later
-        
-        // Load sprites
-        let mut animation_frames = HashMap::new();
-        
-        // In a real implementation, you would load sprites from a directory
-        // Here we just create placeholders
-        let idle_right = vec![image::DynamicImage::new_rgba8(100, 100)];
-        animation_frames.insert("idle_right".to_string(), idle_right);
-        
-        let idle_left = vec![image::DynamicImage::new_rgba8(100, 100)];
-        animation_frames.insert("idle_left".to_string(), idle_left);
-        
-        let sitting = vec![image::DynamicImage::new_rgba8(100, 100)];
-        animation_frames.insert("sitting".to_string(), sitting);
-        
-        // Add movement animations
-        let move_right = vec![image::DynamicImage::new_rgba8(100, 100)];
-        animation_frames.insert("move_right".to_string(), move_right);
+        // Get screen dimensions, falling back to 1080p when unknown
+        let (screen_width, screen_height) = window_info
+            .work_area()
+            .map(|area| (area.right, area.bottom))
+            .unwrap_or((1920, 1080));
         
-        let move_left = vec![image::DynamicImage::new_rgba8(100, 100)];
-        animation_frames.insert("move_left".to_string(), move_left);
+        // Load sprites from the configured pack
+        let sprites = load_sprites(&config.display.sprite_dir);
         
         // Initialize commenter
-        let commenter = Commenter::new();
+        // Separate seeds keep the cat's moves independent of how often it comments
+        let commenter = Commenter::new(&config.comments, seed.map(|seed| seed.wrapping_add(1)));
+        let speaker = Speaker::new(speech::backend(config), config);
+        let sounds = SoundPlayer::new(
+            load_sounds(&config.display.sprite_dir),
+            Box::new(PlayerProcess::new()),
+            config,
+            clock.clone(),
+        );
+        let now = clock.now();
         
         let mut controller = Self {
-            animation_frames,
+            sprite_dir: config.display.sprite_dir.clone(),
+            animation_frames: HashMap::new(),
+            frame_delays: HashMap::new(),
+            frame_handles: HashMap::new(),
             current_animation: "idle_right".to_string(),
             frame_index: 0,
             max_frame_index: 0,
+            next_frame_at: now,
+            sounds,
             
-            pos: Position { x: 20 },
+            movement: config.movement.clone(),
+            pos: Position { x: config.display.start_x, y: screen_height },
             direction: Direction::Right,
             animation_state: AnimationState::Idle,
             
-            last_update: Instant::now(),
-            idle_delay: 0,
-            max_idle_delay: 1000,
+            needs: Needs::default(),
+            resting: false,
+            running: false,
+            
+            clock,
+            rng: timing::rng(seed),
+            last_tick: now,
+            next_step_at: now + config.movement.step_interval(),
             
             commenter,
+            persona: config.comments.persona.clone(),
+            last_comment_at: None,
+            comment_job: None,
+            comment_abort: None,
+            shutting_down: false,
+            muted: false,
+            speaker,
+            comment_schedule: config.comments.schedule(),
+            next_comment_at: now,
             chat_response: None,
             chat_visible: false,
-            chat_duration: 0,
-            chat_max_duration: 500,
+            chat_shown_at: now,
+            chat_max_duration: Duration::ZERO,
+            bubble: None,
+            activity: ActivityWatcher::new(&config.events, now),
+            pending_event: None,
             
             screen_width,
+            screen_height,
+            
+            window_info,
+            ledges: Vec::new(),
+            perch: None,
+            next_ledge_refresh: now,
+            jump: None,
+            fall_speed: 0.0,
             
             is_transitioning: false,
-            transition_complete: 0,
+            transition_started: now,
             transition_x: 0,
             
             is_disappeared: false,
-            disappear_timer: 0,
-            disappear_duration: 0,
+            reappear_at: now,
+            
+            quiet: config.quiet.clone(),
+            privacy: config.privacy.clone(),
+            quiet_reason: None,
+            snoozed_until: None,
+            fullscreen: false,
+            sleep_started: now,
+            
+            debug: false,
+            reaction_ends_at: None,
         };
         
         // Generate an initial comment when the application starts
         // We can't call generate_comment directly here because it's not thread-safe
-        // So we'll set a flag to generate a comment on the first update
-        let mut rng = rand::thread_rng();
-        if rng.gen_bool(0.8) { // 80% chance to make an initial comment
+        // So we schedule it shortly after the first update
+        controller.set_sprites(sprites);
+        controller.next_comment_at = if controller.rng.gen_bool(config.comments.initial_comment_chance) {
             info!("Setting up initial comment");
-            let delay = rng.gen_range(50..150);
-            controller.idle_delay = controller.max_idle_delay - delay;
-        }
+            now + Duration::from_millis(controller.rng.gen_range(800..2400))
+        } else {
+            now + controller.comment_schedule.next_delay(&mut controller.rng)
+        };
         
         controller
     }
     
+    // Cấu hình mới có hiệu lực từ bước/bình luận tiếp theo
+    pub fn apply_config(&mut self, config: &Config) {
+        if config.display.sprite_dir != self.sprite_dir {
+            self.sprite_dir = config.display.sprite_dir.clone();
+            self.set_sprites(load_sprites(&self.sprite_dir));
+            self.sounds.set_pack(load_sounds(&self.sprite_dir));
+        }
+        self.sounds.apply_config(config);
+        self.movement = config.movement.clone();
+        self.comment_schedule = config.comments.schedule();
+        // Lịch cũ có thể hẹn xa hơn max mới
+        self.next_comment_at = self.next_comment_at.min(self.clock.now() + self.comment_schedule.max);
+        self.commenter.apply_config(&config.comments);
+        self.persona = config.comments.persona.clone();
+        self.speaker.apply_config(config);
+        self.quiet = config.quiet.clone();
+        self.privacy = config.privacy.clone();
+        self.activity.apply_config(&config.events);
+    }
+    
+    pub fn state(&self) -> PetState {
+        PetState {
+            saved_at: self.clock.local_time(),
+            x: self.pos.x,
+            y: self.pos.y,
+            direction: self.direction.clone(),
+            needs: self.needs.clone(),
+            last_comment_at: self.last_comment_at,
+            persona: self.persona.clone(),
+            skin: self.sprite_dir.clone(),
+        }
+    }
+    
+    // Tiếp tục từ lần chạy trước; thời gian tắt máy mèo ngủ, đói dần và
+    // thấy cô đơn
+    pub fn restore(&mut self, state: &PetState) -> Result<()> {
+        let now = self.clock.now();
+        let local = self.clock.local_time();
+        let offline = state.offline_for(local);
+        info!("Restoring cat state saved {:?} ago", offline);
+        
+        self.pos.x = state.x.clamp(0, (self.screen_width - CAT_SIZE).max(0));
+        self.direction = state.direction.clone();
+        self.needs = state.needs.clone();
+        self.needs.drift(offline, true);
+        
+        // Không bình luận lại quá sớm sau lần trước
+        self.last_comment_at = state.last_comment_at;
+        if let Some(since) = state.last_comment_at.and_then(|at| (local - at).to_std().ok()) {
+            if since < self.comment_schedule.min {
+                self.next_comment_at = self.next_comment_at.max(now + (self.comment_schedule.min - since));
+            }
+        }
+        
+        self.set_animation(&format!("idle_{}", self.direction))?;
+        // Đứng trên cửa sổ lúc tắt: rơi xuống chỗ đứng gần nhất bên dưới
+        if state.y < self.ground_y() {
+            self.pos.y = state.y;
+            self.start_fall()?;
+        }
+        Ok(())
+    }
+    
     // Update animation
     pub fn handle_animation(&mut self) -> Result<()> {
+        if self.shutting_down {
+            return Ok(());
+        }
+        let now = self.clock.now();
+        let dt = now.saturating_duration_since(self.last_tick).min(MAX_FRAME_TIME);
+        self.last_tick = now;
+        self.advance_frame(now);
+        self.needs.drift(dt, matches!(self.animation_state, AnimationState::Sleeping));
+        
+        // Re-read window positions now and then so ledges follow moved windows
+        if now >= self.next_ledge_refresh {
+            self.next_ledge_refresh = now + LEDGE_REFRESH_INTERVAL;
+            self.refresh_ledges()?;
+        }
+        
+        let reason = self.quiet_reason(now);
+        if reason != self.quiet_reason {
+            self.set_quiet(reason, now);
+        }
+        
         // Process different sprite states
         match self.animation_state {
             AnimationState::Idle => {
-                self.handle_idle()?;
+                self.handle_idle(now)?;
             }
             AnimationState::Transitioning => {
-                self.handle_transitioning()?;
+                self.handle_transitioning(now)?;
+            }
+            AnimationState::Jumping => {
+                self.handle_jumping(now)?;
+            }
+            AnimationState::Falling => {
+                self.handle_falling(dt)?;
+            }
+            AnimationState::Sleeping => {
+                self.handle_sleeping(now)?;
+            }
+        }
+        
+        // Giữ chat thêm một chút sau khi TTS đọc xong
+        let mut speech_finished = false;
+        self.speaker.poll(|_| speech_finished = true);
+        if speech_finished && self.chat_visible {
+            let spoken = now.saturating_duration_since(self.chat_shown_at);
+            if let Some(text) = &self.chat_response {
+                self.chat_max_duration = timing::chat_duration(text, Some(spoken));
             }
         }
         
         // Update chat if visible
         if self.chat_visible {
-            self.chat_duration += 1;
-            if self.chat_duration >= self.chat_max_duration {
+            // Fade the bubble out first, then hide the chat; "Thinking..."
+            // stays until the AI answers
+            let shown_for = now.saturating_duration_since(self.chat_shown_at);
+            let speaking = self.speaker.is_speaking() && shown_for < MAX_SPEECH_WAIT;
+            if shown_for >= self.chat_max_duration && !speaking && !self.is_thinking() {
+                if let Some(bubble) = self.bubble.as_mut() {
+                    bubble.start_fade(now);
+                }
+            }
+            
+            if self.bubble.as_ref().is_none_or(|bubble| bubble.is_faded(now)) {
                 info!("Chat display timeout, hiding chat");
                 self.chat_visible = false;
-                self.chat_duration = 0;
+                self.bubble = None;
                 
-                // After a chat ends, reduce the time until the next comment
-                let mut rng = rand::thread_rng();
-                let boost = rng.gen_range(200..500);
-                self.idle_delay = self.max_idle_delay - boost;
+                // Space the next comment from the end of this one
+                let delay = self.comment_schedule.next_delay(&mut self.rng);
+                info!("Next comment in {:?}", delay);
+                self.next_comment_at = now + delay;
             }
         }
         
         Ok(())
     }
     
-    fn handle_idle(&mut self) -> Result<()> {
-        self.idle_delay += 1;
+    fn handle_idle(&mut self, now: Instant) -> Result<()> {
+        if self.quiet_reason.is_some() {
+            return self.go_to_sleep(now);
+        }
         
-        // Make the cat more likely to comment by reducing the delay threshold
-        if self.idle_delay >= self.max_idle_delay / 2 && !self.chat_visible {
-            // Check if we should generate a comment - increased probability
-            let now = Instant::now();
-            let elapsed = now.duration_since(self.last_update);
-            
-            // Generate comment more frequently - every 20 seconds instead of 60
-            if elapsed > Duration::from_secs(20) {
-                self.last_update = now;
-                let mut rng = rand::thread_rng();
-                if rng.gen_bool(0.5) { // 50% chance instead of 25%
-                    info!("Triggering comment generation due to time elapsed");
-                    return self.generate_comment();
-                }
+        if self.reaction_ends_at.is_some_and(|end| now >= end) {
+            self.reaction_ends_at = None;
+            self.set_animation("sitting")?;
+        }
+        
+        if self.chat_visible || self.is_thinking() {
+            return Ok(());
+        }
+        
+        if self.needs.energy < TIRED {
+            info!("Cat is tired, taking a nap");
+            self.resting = true;
+            return self.go_to_sleep(now);
+        }
+        
+        // Sự kiện được ưu tiên, nhưng vẫn cách bình luận trước ít nhất schedule.min
+        if let Some((event, seen_at)) = self.pending_event.take() {
+            if now.saturating_duration_since(seen_at) > MAX_EVENT_AGE {
+                info!("Dropping stale {} event", event.kind().name());
+            } else if now.saturating_duration_since(self.chat_shown_at) < self.comment_schedule.min {
+                self.pending_event = Some((event, seen_at));
+            } else {
+                info!("Triggering comment on {} event", event.kind().name());
+                self.activity.commented(event.kind(), now);
+                metrics::increment(&format!("{}.{}", metrics::EVENT_COMMENTS, event.kind().name()));
+                self.commenter.set_context(Some(event.context()));
+                let result = self.generate_comment();
+                self.commenter.set_context(None);
+                return result;
             }
         }
         
-        if self.idle_delay >= self.max_idle_delay && !self.chat_visible {
+        if now >= self.next_comment_at {
+            info!("Triggering scheduled comment generation");
+            return self.generate_comment();
+        }
+        
+        if now >= self.next_step_at {
+            // Chán thì chạy lung tung cho tới khi đỡ chán
+            if !self.running && self.needs.boredom >= BORED {
+                info!("Cat is bored, running around");
+                self.running = true;
+            } else if self.running && self.needs.boredom < ENTERTAINED {
+                self.running = false;
+                self.set_animation(&format!("idle_{}", self.direction))?;
+            }
+            
             // Move directly instead of changing to Moving state
-            self.idle_delay = 0;
+            if self.running {
+                self.next_step_at = now + self.movement.step_interval() / RUN_SPEEDUP;
+                self.needs.exercise(RUN_EFFORT);
+                let name = format!("move_{}", self.direction);
+                if self.current_animation != name {
+                    self.set_animation(&name)?;
+                }
+            } else {
+                self.next_step_at = now + self.movement.step_interval();
+            }
             
             // Move based on current direction
             match self.direction {
                 Direction::Right => {
-                    self.pos.x += 5;
+                    self.pos.x += self.movement.step_pixels;
                     // Check if screen transition is needed
                     if self.pos.x > self.screen_width - 70 {
-                        self.start_transition(Direction::Right)?;
+                        return self.start_transition(Direction::Right);
                     }
                 }
                 Direction::Left => {
-                    self.pos.x -= 5;
+                    self.pos.x -= self.movement.step_pixels;
                     // Check if screen transition is needed
                     if self.pos.x < -30 {
-                        self.start_transition(Direction::Left)?;
+                        return self.start_transition(Direction::Left);
                     }
                 }
             }
             
-            // Small chance to comment after each movement
-            let mut rng = rand::thread_rng();
-            if rng.gen_bool(0.1) { // 10% chance to comment after movement
-                info!("Triggering comment generation after movement");
-                self.generate_comment()?;
+            // Walking off the end of a window makes the cat fall
+            if let Some(perch) = self.perch {
+                if !perch.contains_x(self.center_x()) {
+                    info!("Cat walked off a window edge");
+                    return self.start_fall();
+                }
+            }
+            
+            // Sometimes jump up onto a window ahead
+            if self.rng.gen_bool(self.movement.jump_chance) {
+                self.try_jump()?;
             }
         }
         
         Ok(())
     }
     
-    fn handle_transitioning(&mut self) -> Result<()> {
+    // Lý do không làm phiền lúc này, nếu có
+    fn quiet_reason(&self, now: Instant) -> Option<&'static str> {
+        if self.snoozed_until.is_some_and(|until| now < until) {
+            Some("snoozed")
+        } else if self.quiet.fullscreen && self.fullscreen {
+            Some("fullscreen window")
+        } else if self.quiet.is_quiet(self.clock.local_time().time()) {
+            Some("quiet hours")
+        } else {
+            None
+        }
+    }
+    
+    fn set_quiet(&mut self, reason: Option<&'static str>, now: Instant) {
+        let was_quiet = self.quiet_reason.is_some();
+        self.quiet_reason = reason;
+        self.sounds.set_suppressed(reason.is_some());
+        
+        match reason {
+            Some(reason) => {
+                info!("Do not disturb ({}): comments paused", reason);
+                self.pending_event = None;
+                // Bỏ câu đang đọc và các câu đang chờ, không đọc lại sau
+                self.speaker.stop();
+                if let Some(bubble) = self.bubble.as_mut() {
+                    bubble.start_fade(now);
+                }
+            }
+            None if was_quiet => {
+                info!("Do not disturb ended");
+                // Comments missed while quiet are dropped, not caught up on
+                self.next_comment_at = now + self.comment_schedule.next_delay(&mut self.rng);
+            }
+            None => {}
+        }
+    }
+    
+    // Bấm S: tạm tắt bình luận, bấm lần nữa để bật lại
+    pub fn toggle_snooze(&mut self) {
+        let now = self.clock.now();
+        if self.snoozed_until.is_some_and(|until| now < until) {
+            info!("Snooze cancelled");
+            self.snoozed_until = None;
+        } else {
+            info!("Snoozed for {:?}", self.quiet.snooze());
+            self.snoozed_until = Some(now + self.quiet.snooze());
+        }
+    }
+    
+    pub fn is_snoozed(&self) -> bool {
+        self.snoozed_until.is_some_and(|until| self.clock.now() < until)
+    }
+    
+    pub fn set_muted(&mut self, muted: bool) {
+        info!("Sound {}", if muted { "muted" } else { "unmuted" });
+        self.muted = muted;
+        self.speaker.set_muted(muted);
+        self.sounds.set_muted(muted);
+    }
+    
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+    
+    pub fn toggle_debug(&mut self) {
+        self.debug = !self.debug;
+        info!("Debug overlay {}", if self.debug { "on" } else { "off" });
+    }
+    
+    // Bình luận ngay khi mèo rảnh, thay vì chờ tới lịch
+    pub fn comment_now(&mut self) -> Result<()> {
+        self.check_awake()?;
+        self.next_comment_at = self.clock.now();
+        Ok(())
+    }
+    
+    // Câu từ API điều khiển: hiện và đọc như một bình luận
+    pub fn say(&mut self, text: &str) -> Result<()> {
+        self.check_awake()?;
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(anyhow!("Nothing to say"));
+        }
+        info!("Saying: {}", text);
+        self.speaker.say(text);
+        self.show_chat(text.to_string());
+        self.sounds.play(SoundEvent::Comment);
+        Ok(())
+    }
+    
+    // Một lệnh (build, test...) vừa chạy xong: nhảy mừng hoặc ngã ngửa một
+    // lần, rồi nói một câu theo persona
+    pub fn react_to_command(&mut self, command: &str, exit_code: Option<i32>) -> Result<()> {
+        self.check_awake()?;
+        let passed = exit_code == Some(0);
+        info!("'{}' {}", command, if passed { "passed" } else { "failed" });
+        
+        // Đang nhảy, rơi hay ngủ thì chỉ nói thôi
+        if matches!(self.animation_state, AnimationState::Idle) && !self.is_disappeared {
+            let animation = if passed { format!("jump_{}", self.direction) } else { "fall".to_string() };
+            if self.set_animation(&animation).is_err() {
+                self.set_animation("sitting")?;
+            }
+            self.reaction_ends_at = Some(self.clock.now() + self.animation_duration(&self.current_animation));
+        }
+        
+        let comment = self.commenter.finished_comment(&self.persona, command, exit_code);
+        self.speaker.say(&comment);
+        self.show_chat(comment);
+        Ok(())
+    }
+    
+    // Chạy tới khi mèo chuyển sang việc khác
+    pub fn play(&mut self, animation: &str) -> Result<()> {
+        self.check_awake()?;
+        self.set_animation(animation)
+    }
+    
+    // Ngủ như khi bấm S, bình luận tạm dừng tới khi hết giờ hoặc wake()
+    pub fn sleep(&mut self) {
+        info!("Sent to sleep for {:?}", self.quiet.snooze());
+        self.snoozed_until = Some(self.clock.now() + self.quiet.snooze());
+    }
+    
+    pub fn wake(&mut self) -> Result<()> {
+        info!("Woken up");
+        self.snoozed_until = None;
+        self.resting = false;
+        match self.quiet_reason(self.clock.now()) {
+            Some(reason) => Err(anyhow!("Still quiet: {}", reason)),
+            None => Ok(()),
+        }
+    }
+    
+    fn check_awake(&self) -> Result<()> {
+        if self.shutting_down {
+            return Err(anyhow!("The cat is shutting down"));
+        }
+        match self.quiet_reason {
+            Some(reason) => Err(anyhow!("Not now: {}", reason)),
+            None => Ok(()),
+        }
+    }
+    
+    fn go_to_sleep(&mut self, now: Instant) -> Result<()> {
+        let name = match self.direction {
+            Direction::Left => "go_sleep_left",
+            Direction::Right => "go_sleep",
+        };
+        self.animation_state = AnimationState::Sleeping;
+        self.sleep_started = now;
+        self.running = false;
+        // Bộ sprite không có animation ngủ thì ngồi yên
+        if self.set_animation(name).is_err() {
+            self.set_animation("sitting")?;
+        }
+        Ok(())
+    }
+    
+    fn handle_sleeping(&mut self, now: Instant) -> Result<()> {
+        if self.quiet_reason.is_none() && (!self.resting || self.needs.energy >= RESTED) {
+            info!("Cat woke up");
+            self.resting = false;
+            self.animation_state = AnimationState::Idle;
+            self.next_step_at = now + self.movement.step_interval();
+            self.sounds.play(SoundEvent::Wake);
+            return self.set_animation(&format!("idle_{}", self.direction));
+        }
+        
+        // Nằm xuống xong thì ngủ
+        if self.current_animation.starts_with("go_sleep")
+            && now >= self.sleep_started + self.animation_duration(&self.current_animation)
+            && self.animation_frames.contains_key("sleeping_1")
+        {
+            self.set_animation("sleeping_1")?;
+        }
+        Ok(())
+    }
+    
+    fn handle_transitioning(&mut self, now: Instant) -> Result<()> {
         // Handle screen transition
         if self.is_disappeared {
-            self.disappear_timer += 1;
-            if self.disappear_timer >= self.disappear_duration {
+            if now >= self.reappear_at {
                 self.is_disappeared = false;
                 info!("Cat reappeared");
+                self.sounds.play(SoundEvent::Wake);
                 
                 // Reset position
                 match self.direction {
@@ -264,30 +880,35 @@ impl SpriteController {
                 
                 self.is_transitioning = false;
                 self.animation_state = AnimationState::Idle;
+                self.pos.y = self.ground_y();
+                self.next_step_at = now + self.movement.step_interval();
             }
             return Ok(());
         }
         
-        self.transition_complete += 2;
+        // 0.0 → 1.0 over the transition; the cat leaves in the first half
+        // and comes back in the second
+        let elapsed = now.saturating_duration_since(self.transition_started);
+        let progress = (elapsed.as_secs_f32() / TRANSITION_DURATION.as_secs_f32()).min(1.0);
         
         match self.direction {
             Direction::Right => {
                 // Move from right to left
-                if self.transition_complete <= 50 {
-                    let ratio = self.transition_complete as f32 / 50.0;
+                if progress <= 0.5 {
+                    let ratio = progress * 2.0;
                     self.transition_x = (self.screen_width - 30) + (ratio * 100.0) as i32;
                 } else {
-                    let ratio = (self.transition_complete - 50) as f32 / 50.0;
+                    let ratio = progress * 2.0 - 1.0;
                     self.transition_x = -70 + (ratio * 100.0) as i32;
                 }
             }
             Direction::Left => {
                 // Move from left to right
-                if self.transition_complete <= 50 {
-                    let ratio = self.transition_complete as f32 / 50.0;
+                if progress <= 0.5 {
+                    let ratio = progress * 2.0;
                     self.transition_x = -70 + (ratio * -30.0) as i32;
                 } else {
-                    let ratio = (self.transition_complete - 50) as f32 / 50.0;
+                    let ratio = progress * 2.0 - 1.0;
                     self.transition_x = (self.screen_width + 30) - (ratio * 100.0) as i32;
                 }
             }
@@ -295,46 +916,343 @@ impl SpriteController {
         
         self.pos.x = self.transition_x;
         
-        if self.transition_complete >= 100 {
+        if progress >= 1.0 {
             self.is_transitioning = false;
             self.direction = self.direction.opposite();
             self.animation_state = AnimationState::Idle;
-            self.set_animation(&format!("idle_{}", self.direction.to_string()))?;
+            self.set_animation(&format!("idle_{}", self.direction))?;
+            self.next_step_at = now + self.movement.step_interval();
         }
         
         Ok(())
     }
     
     fn start_transition(&mut self, from_direction: Direction) -> Result<()> {
+        // Screen transitions always happen at ground level
+        self.perch = None;
+        self.pos.y = self.ground_y();
         self.is_transitioning = true;
         self.direction = from_direction;
-        self.transition_complete = 0;
+        self.transition_started = self.clock.now();
         self.animation_state = AnimationState::Transitioning;
         
         // Determine if the cat should randomly disappear
-        let mut rng = rand::thread_rng();
-        if rng.gen_bool(0.3) { // 30% chance
+        if self.rng.gen_bool(self.movement.disappear_chance) {
+            let duration = Duration::from_millis(self.rng.gen_range(1600..4800));
             self.is_disappeared = true;
-            self.disappear_timer = 0;
-            self.disappear_duration = rng.gen_range(100..300);
-            info!("Cat disappeared, will reappear after {} frames", self.disappear_duration);
+            self.reappear_at = self.transition_started + duration;
+            info!("Cat disappeared, will reappear after {:?}", duration);
+        }
+        
+        Ok(())
+    }
+    
+    fn handle_jumping(&mut self, now: Instant) -> Result<()> {
+        let Some(jump) = self.jump.as_ref() else {
+            return self.start_fall();
+        };
+        
+        let elapsed = now.saturating_duration_since(jump.started);
+        let t = (elapsed.as_secs_f32() / JUMP_DURATION.as_secs_f32()).min(1.0);
+        let (from_x, from_y) = jump.from;
+        let (to_x, to_y) = jump.to;
+        
+        // Parabolic arc between take-off and landing point
+        let arc = JUMP_ARC * 4.0 * t * (1.0 - t);
+        self.pos.x = from_x + ((to_x - from_x) as f32 * t) as i32;
+        self.pos.y = from_y + ((to_y - from_y) as f32 * t - arc) as i32;
+        
+        if t >= 1.0 {
+            let ledge = jump.ledge;
+            self.jump = None;
+            
+            // The window may have moved or closed during the jump
+            match window_handler::support_at(&self.ledges, self.center_x(), ledge.y, LEDGE_TOLERANCE) {
+                Some(ledge) => self.land(Some(ledge))?,
+                None => self.start_fall()?,
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn handle_falling(&mut self, dt: Duration) -> Result<()> {
+        let dt = dt.as_secs_f32();
+        self.fall_speed = (self.fall_speed + GRAVITY * dt).min(MAX_FALL_SPEED);
+        let next_y = self.pos.y + (self.fall_speed * dt).round() as i32;
+        let ground = self.ground_y();
+        
+        if let Some(ledge) = window_handler::landing_between(&self.ledges, self.center_x(), self.pos.y, next_y) {
+            return self.land(Some(ledge));
+        }
+        
+        if next_y >= ground {
+            return self.land(None);
+        }
+        
+        self.pos.y = next_y;
+        Ok(())
+    }
+    
+    fn try_jump(&mut self) -> Result<bool> {
+        let target = window_handler::jump_target(
+            &self.ledges,
+            self.center_x(),
+            self.pos.y,
+            self.direction.sign(),
+            JUMP_REACH,
+            JUMP_MAX_HEIGHT,
+            CAT_SIZE / 4,
+        );
+        
+        let Some((landing_x, ledge)) = target else {
+            return Ok(false);
+        };
+        
+        info!("Cat jumps onto window edge at y={}", ledge.y);
+        self.jump = Some(Jump {
+            from: (self.pos.x, self.pos.y),
+            to: (landing_x - CAT_SIZE / 2, ledge.y),
+            ledge,
+            started: self.clock.now(),
+        });
+        self.perch = None;
+        self.animation_state = AnimationState::Jumping;
+        self.set_animation(&format!("jump_{}", self.direction))?;
+        self.sounds.play(SoundEvent::Jump);
+        self.needs.exercise(JUMP_EFFORT);
+        
+        Ok(true)
+    }
+    
+    fn start_fall(&mut self) -> Result<()> {
+        self.perch = None;
+        self.jump = None;
+        self.fall_speed = 0.0;
+        self.animation_state = AnimationState::Falling;
+        self.set_animation("fall")
+    }
+    
+    fn land(&mut self, ledge: Option<Ledge>) -> Result<()> {
+        self.pos.y = ledge.map(|l| l.y).unwrap_or_else(|| self.ground_y());
+        self.perch = ledge;
+        self.fall_speed = 0.0;
+        self.animation_state = AnimationState::Idle;
+        self.next_step_at = self.clock.now() + self.movement.step_interval();
+        self.sounds.play(SoundEvent::Land);
+        self.set_animation(&format!("idle_{}", self.direction))
+    }
+    
+    // Cửa sổ trong danh sách riêng tư coi như không nhìn thấy: không có sự
+    // kiện nào về nó, tiêu đề không vào prompt
+    fn watch_activity(&mut self, foreground: Option<&ForegroundWindow>) {
+        let now = self.clock.now();
+        let window = foreground.filter(|window| !self.privacy.denies(&window.title));
+        if let Some(event) = self.activity.observe(window, self.window_info.idle_time(), now) {
+            info!("Activity event: {}", event.kind().name());
+            self.pending_event = Some((event, now));
+        }
+    }
+    
+    fn refresh_ledges(&mut self) -> Result<()> {
+        // Taskbars and panels may move or resize
+        if let Some(area) = self.window_info.work_area() {
+            self.screen_width = area.right;
+            self.screen_height = area.bottom;
+        }
+        
+        let foreground = self.window_info.foreground_window();
+        self.fullscreen = foreground.as_ref().is_some_and(|window| window.fullscreen);
+        self.watch_activity(foreground.as_ref());
+        let windows = self.window_info.visible_windows();
+        self.ledges = window_handler::find_ledges(&windows, MIN_LEDGE_WIDTH)
+            .into_iter()
+            // Keep room above the edge for the cat itself
+            .filter(|l| l.y >= CAT_SIZE && l.y < self.ground_y())
+            .collect();
+        
+        // Follow the window we're standing on, or fall if it went away
+        if let Some(perch) = self.perch {
+            if !matches!(self.animation_state, AnimationState::Idle | AnimationState::Sleeping) {
+                return Ok(());
+            }
+            match window_handler::support_at(&self.ledges, self.center_x(), perch.y, LEDGE_TOLERANCE) {
+                Some(ledge) => {
+                    self.perch = Some(ledge);
+                    self.pos.y = ledge.y;
+                }
+                None => {
+                    info!("Window under the cat disappeared");
+                    self.start_fall()?;
+                }
+            }
         }
         
         Ok(())
     }
     
+    fn ground_y(&self) -> i32 {
+        self.screen_height
+    }
+    
+    fn center_x(&self) -> i32 {
+        self.pos.x + CAT_SIZE / 2
+    }
+    
+    // Vị trí mèo và khung chat trong cửa sổ
+    fn bubble_layout(&self) -> BubbleLayout {
+        let cat = WindowRect::new(self.pos.x, self.pos.y - CAT_SIZE, self.pos.x + CAT_SIZE, self.pos.y);
+        let screen = WindowRect::new(0, 0, self.screen_width, self.screen_height);
+        let size = if self.debug {
+            DEBUG_PANEL_SIZE
+        } else {
+            self.bubble.as_ref().map(SpeechBubble::size).unwrap_or((0, 0))
+        };
+        speech_bubble::layout(cat, screen, size, WINDOW_SIZE.1 as i32)
+    }
+    
+    // Vị trí góc trên bên trái của cửa sổ để mèo đứng đúng chỗ trên màn hình
+    pub fn window_position(&self) -> (i32, i32) {
+        self.bubble_layout().window_origin
+    }
+    
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.animation_state.name(),
+            animation: self.current_animation.clone(),
+            frame: self.frame_index,
+            x: self.pos.x,
+            y: self.pos.y,
+            visible: !self.is_disappeared,
+            bubble: self.chat_visible.then(|| self.chat_response.clone()).flatten(),
+            mood: self.needs.mood().name(),
+        }
+    }
+    
+    // Changes whenever hit_region would return something different
+    pub fn frame_key(&self) -> (String, usize, BubbleLayout) {
+        (self.current_animation.clone(), self.frame_index, self.bubble_layout())
+    }
+    
+    // Vùng nhận chuột trong chế độ overlay (toạ độ cửa sổ): các pixel không
+    // trong suốt của sprite và khung chat
+    pub fn hit_region(&self) -> Vec<WindowRect> {
+        let layout = self.bubble_layout();
+        let mut region = Vec::new();
+        
+        if let Some(frame) = self.current_frame() {
+            // The sprite is fitted into the cat's box in view()
+            let target = sprite_rect(frame.width(), frame.height(), layout.cat);
+            let (cat_left, cat_top) = (target.left, target.top);
+            let scale_x = target.width() as f32 / frame.width().max(1) as f32;
+            let scale_y = target.height() as f32 / frame.height().max(1) as f32;
+            region.extend(
+                window_handler::opaque_rects(&frame.to_rgba8(), HIT_ALPHA_THRESHOLD)
+                    .into_iter()
+                    .map(|r| {
+                        WindowRect::new(
+                            cat_left + (r.left as f32 * scale_x).floor() as i32,
+                            cat_top + (r.top as f32 * scale_y).floor() as i32,
+                            cat_left + (r.right as f32 * scale_x).ceil() as i32,
+                            cat_top + (r.bottom as f32 * scale_y).ceil() as i32,
+                        )
+                    }),
+            );
+        }
+        
+        if self.bubble.is_some() {
+            region.push(layout.canvas);
+        }
+        
+        region
+    }
+    
+    fn current_frame(&self) -> Option<&image::DynamicImage> {
+        self.animation_frames
+            .get(&self.current_animation)
+            .and_then(|frames| frames.get(self.frame_index.min(frames.len().saturating_sub(1))))
+    }
+    
+    // Ảnh RGBA của cả cửa sổ, bố cục giống hệt view()
+    #[cfg(test)]
+    pub fn render(&self) -> image::RgbaImage {
+        let layout = self.bubble_layout();
+        let mut canvas = image::RgbaImage::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+        
+        if let Some(bubble) = &self.bubble {
+            bubble.render_into(&mut canvas, &layout, self.clock.now());
+        }
+        if let Some(frame) = self.current_frame() {
+            crate::render::draw_sprite(&mut canvas, frame, layout.cat);
+        }
+        
+        canvas
+    }
+    
     fn set_animation(&mut self, name: &str) -> Result<()> {
         if !self.animation_frames.contains_key(name) {
             return Err(anyhow!("Animation '{}' not found", name));
         }
+        if name != self.current_animation {
+            self.sounds.play_animation(name);
+        }
         
         self.current_animation = name.to_string();
         self.frame_index = 0;
         self.max_frame_index = self.animation_frames[name].len() - 1;
+        self.next_frame_at = self.clock.now() + self.frame_delay();
         
         Ok(())
     }
     
+    fn frame_delay(&self) -> Duration {
+        self.frame_delays
+            .get(&self.current_animation)
+            .and_then(|delays| delays.get(self.frame_index))
+            .copied()
+            .unwrap_or(DEFAULT_FRAME_DELAY)
+    }
+    
+    fn animation_duration(&self, name: &str) -> Duration {
+        self.frame_delays.get(name).map(|delays| delays.iter().sum()).unwrap_or_default()
+    }
+    
+    // Chạy frame tiếp theo của animation hiện tại khi tới giờ
+    fn advance_frame(&mut self, now: Instant) {
+        if now < self.next_frame_at {
+            return;
+        }
+        self.frame_index = if self.frame_index >= self.max_frame_index { 0 } else { self.frame_index + 1 };
+        self.next_frame_at = now + self.frame_delay();
+    }
+    
+    fn set_sprites(&mut self, pack: SpritePack) {
+        // Upload-ready handles are built once; rebuilding them every frame
+        // would give iced a new image to cache on each redraw
+        self.frame_handles = pack
+            .frames
+            .iter()
+            .map(|(name, frames)| {
+                let handles = frames
+                    .iter()
+                    .map(|frame| {
+                        let rgba = frame.to_rgba8();
+                        Handle::from_pixels(rgba.width(), rgba.height(), rgba.into_raw())
+                    })
+                    .collect();
+                (name.clone(), handles)
+            })
+            .collect();
+        self.animation_frames = pack.frames;
+        self.frame_delays = pack.delays;
+        
+        let current = self.current_animation.clone();
+        let name = if self.animation_frames.contains_key(&current) { current } else { "idle_right".to_string() };
+        if let Err(e) = self.set_animation(&name) {
+            error!("Sprite pack has no '{}' animation: {}", name, e);
+        }
+    }
+    
     fn generate_comment(&mut self) -> Result<()> {
         info!("Generating comment");
         
@@ -342,83 +1260,172 @@ impl SpriteController {
         self.animation_state = AnimationState::Idle;
         self.set_animation("sitting")?;
         
-        // Display "Thinking..." in chat
-        self.chat_response = Some("Thinking...".to_string());
-        self.chat_visible = true;
-        
-        // Call commenter to get a comment
-        match self.commenter.generate_comment() {
-            Ok(comment) => {
-                info!("Comment generated: {}", comment);
-                self.chat_response = Some(comment);
-                self.chat_duration = 0;
-                
-                // TTS will be performed here
-                if let Err(e) = self.commenter.speak_comment(&self.chat_response.clone().unwrap_or_default()) {
-                    error!("TTS failed: {}", e);
-                    // Continue even if TTS fails
-                }
+        self.last_comment_at = Some(self.clock.local_time());
+        self.commenter.set_mood(self.needs.mood());
+        let private = self
+            .window_info
+            .foreground_window()
+            .is_some_and(|window| self.privacy.denies(&window.title));
+        // Không ghi tiêu đề vào log: chính nó là thứ người dùng muốn giữ kín
+        let job = if private {
+            info!("Focused window is on the privacy deny-list, not taking a screenshot");
+            None
+        } else {
+            self.commenter.comment_job()
+        };
+        match job {
+            Some(job) => {
+                // Display "Thinking..." in chat until the AI answers
+                self.show_chat("Thinking...".to_string());
+                self.comment_job = Some(job);
             }
-            Err(e) => {
-                error!("Failed to generate comment: {}", e);
-                // Use sample responses
-                let sample_responses = [
-                    "Meow! What are you doing? That looks interesting!",
-                    "Hmm, humans are so strange with the things they look at on screens.",
-                    "Hey, I see you're working hard. But don't forget to feed me!",
-                ];
-                
-                let mut rng = rand::thread_rng();
-                self.chat_response = Some(sample_responses.choose(&mut rng).unwrap().to_string());
+            None => {
+                let comment = self.commenter.fallback_comment();
+                self.say_comment(comment);
             }
         }
         
-        // Make the chat visible for longer time
-        self.chat_max_duration = 800; // Increased from 500
-        
         Ok(())
     }
     
+    // Chụp màn hình và gọi API ở luồng riêng để mèo vẫn chuyển động. Display
+    // chạy future này và trả kết quả về comment_ready; shutdown() huỷ nó.
+    pub fn take_comment_job(&mut self) -> Option<impl Future<Output = Option<String>> + Send + 'static> {
+        let job = self.comment_job.take()?;
+        let (sender, receiver) = oneshot::channel();
+        let (response, abort) = future::abortable(receiver);
+        let cancelled = abort.clone();
+        std::thread::spawn(move || {
+            if !cancelled.is_aborted() {
+                // Nobody is waiting any more after a shutdown
+                let _ = sender.send(job.run().ok());
+            }
+        });
+        self.comment_abort = Some(abort);
+        Some(response.map(|response| response.ok().and_then(Result::ok).flatten()))
+    }
+    
+    // AI đã trả lời; None khi lỗi, dùng câu dự phòng
+    pub fn comment_ready(&mut self, response: Option<String>) {
+        self.comment_abort = None;
+        if self.shutting_down {
+            return;
+        }
+        let comment = self.commenter.finish_comment(response);
+        if self.quiet_reason.is_some() {
+            info!("Dropping comment, do not disturb");
+            return;
+        }
+        self.say_comment(comment);
+    }
+    
+    fn is_thinking(&self) -> bool {
+        self.comment_job.is_some() || self.comment_abort.is_some()
+    }
+    
+    fn say_comment(&mut self, comment: String) {
+        info!("Comment generated: {}", comment);
+        
+        // Chat stays up until the speech finishes (see handle_animation)
+        self.speaker.say(&comment);
+        self.show_chat(comment);
+        self.sounds.play(SoundEvent::Comment);
+    }
+    
+    // Người dùng bấm vào mèo
+    pub fn pet(&mut self) {
+        info!("Cat was petted");
+        self.needs.pet();
+        self.sounds.play(SoundEvent::Pet);
+    }
+    
+    pub fn feed(&mut self) {
+        info!("Cat was fed");
+        self.needs.feed();
+    }
+    
+    // Chương trình được mở lần nữa trong khi mèo đang chạy
+    pub fn greet(&mut self) {
+        if self.shutting_down || self.quiet_reason.is_some() {
+            return;
+        }
+        self.sounds.play(SoundEvent::Wake);
+        self.show_chat("Meow? I'm already here!".to_string());
+    }
+    
+    // Dừng đọc, huỷ request tới AI và bỏ mọi bình luận còn chờ trước khi thoát
+    pub fn shutdown(&mut self) {
+        info!("Stopping the cat");
+        self.shutting_down = true;
+        self.comment_job = None;
+        if let Some(abort) = self.comment_abort.take() {
+            info!("Cancelling the comment request");
+            abort.abort();
+        }
+        self.speaker.stop();
+        self.chat_visible = false;
+    }
+    
+    // Hiện chat đủ lâu để đọc hết, hoặc tới khi TTS đọc xong
+    fn show_chat(&mut self, text: String) {
+        let now = self.clock.now();
+        self.bubble = Some(SpeechBubble::new(&text, now));
+        self.chat_max_duration = timing::chat_duration(&text, None);
+        self.chat_response = Some(text);
+        self.chat_visible = true;
+        self.chat_shown_at = now;
+    }
+    
     // View function cho Iced
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         // Lấy frame hiện tại từ animation hiện tại
         let current_animation = &self.current_animation;
         if let Some(frames) = self.animation_frames.get(current_animation) {
             let current_frame = &frames[self.frame_index.min(frames.len() - 1)];
             
-            // Chuyển đổi DynamicImage thành Handle cho iced
-            let image_handle = iced::widget::image::Handle::from_memory(current_frame.to_rgba8().to_vec());
+            // Handle đã tạo sẵn trong set_sprites
+            let image_handle = self
+                .frame_handles
+                .get(current_animation)
+                .and_then(|handles| handles.get(self.frame_index))
+                .cloned()
+                .unwrap_or_else(|| {
+                    let rgba = current_frame.to_rgba8();
+                    Handle::from_pixels(rgba.width(), rgba.height(), rgba.into_raw())
+                });
             
-            let mut content = Column::new()
-                .width(Length::Fill)
-                .height(Length::Fill);
-            
-            // Tính toán vị trí tương đối trong cửa sổ
-            // Do mèo chỉ nên hiển thị trong cửa sổ, nên giới hạn pos.x
-            let relative_x = self.pos.x.max(0).min(200 - 100); // 200 là width, 100 là width của hình mèo
-            
-            // Hiển thị sprite mèo với vị trí dựa trên pos.x
             let cat_image = Image::new(image_handle)
-                .width(Length::Fixed(100.0))
-                .height(Length::Fixed(100.0));
+                .width(Length::Fixed(CAT_SIZE as f32))
+                .height(Length::Fixed(CAT_SIZE as f32));
             
-            // Tạo container cho hình ảnh mèo với padding trái để tạo vị trí x
-            let cat_container = Container::new(cat_image)
-                .width(Length::Fill)
-                .padding([0, 0, 0, relative_x as u16]);
+            // Cửa sổ đi theo mèo (xem window_position); mèo và khung chat được
+            // đặt theo bubble_layout
+            let layout = self.bubble_layout();
+            let chat_bubble = match &self.bubble {
+                _ if self.debug => self.debug_panel(&layout),
+                Some(bubble) => bubble.view(&layout, self.clock.now()),
+                None => return placed(cat_image, layout.cat.left, layout.cat.top),
+            };
             
-            content = content.push(cat_container);
+            let content: Element<'_, Message> = match layout.placement {
+                Placement::Above => Column::new()
+                    .push(placed(chat_bubble, layout.canvas.left, layout.canvas.top))
+                    .push(placed(cat_image, layout.cat.left, 0))
+                    .into(),
+                Placement::Right => Row::new()
+                    .push(placed(cat_image, layout.cat.left, layout.cat.top))
+                    .push(placed(chat_bubble, layout.canvas.left - layout.cat.right, layout.canvas.top))
+                    .into(),
+                Placement::Left => Row::new()
+                    .push(placed(chat_bubble, layout.canvas.left, layout.canvas.top))
+                    .push(placed(cat_image, layout.cat.left - layout.canvas.right, layout.cat.top))
+                    .into(),
+            };
             
-            // Nếu chat hiện tại đang hiển thị, thêm chat bubble
-            if self.chat_visible {
-                let chat_text = self.chat_response.clone().unwrap_or_default();
-                let chat_bubble = Text::new(chat_text)
-                    .size(16);
-                
-                content = content.push(chat_bubble);
-            }
-            
-            return content.into();
+            return Container::new(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
         }
         
         // Fallback nếu không có animation
@@ -427,4 +1434,614 @@ impl SpriteController {
             .height(Length::Fill)
             .into()
     }
-} 
\ No newline at end of file
+    
+    // Trạng thái, animation, hẹn giờ, số liệu và lỗi gần nhất, mỗi dòng đủ
+    // ngắn để vừa bảng debug
+    pub fn debug_lines(&self) -> Vec<String> {
+        let now = self.clock.now();
+        let until = |at: Instant| format!("{}s", at.saturating_duration_since(now).as_secs());
+        let frames = self.animation_frames.get(&self.current_animation).map_or(0, Vec::len);
+        let needs = &self.needs;
+        let mut lines = vec![
+            format!("{} {} {}/{}", self.animation_state.name(), self.current_animation, self.frame_index + 1, frames),
+            format!("x {} y {} {} {}", self.pos.x, self.pos.y, self.direction, needs.mood().name()),
+            format!("hun {:.2} en {:.2} aff {:.2} bor {:.2}", needs.hunger, needs.energy, needs.affection, needs.boredom),
+            format!("comment in {} step in {}", until(self.next_comment_at), until(self.next_step_at)),
+        ];
+        if let Some(reason) = self.quiet_reason {
+            lines.push(match self.snoozed_until {
+                Some(until_at) if reason == "snoozed" => format!("quiet: snoozed {}", until(until_at)),
+                _ => format!("quiet: {}", reason),
+            });
+        }
+        if self.is_disappeared {
+            lines.push(format!("away, back in {}", until(self.reappear_at)));
+        }
+        if let Some(text) = self.chat_response.as_ref().filter(|_| self.chat_visible) {
+            lines.push(format!("chat: {}", text));
+        }
+        if let Some((event, _)) = &self.pending_event {
+            lines.push(format!("event: {}", event.kind().name()));
+        }
+        
+        let metrics = metrics::snapshot();
+        let failures = metrics.counters_under(metrics::API_FAILURES);
+        lines.push(format!(
+            "api ok {} fail {} fallback {}",
+            metrics.counter(metrics::API_OK),
+            failures.iter().map(|(_, count)| count).sum::<u64>(),
+            metrics.counter(metrics::FALLBACKS)
+        ));
+        if !failures.is_empty() {
+            let kinds: Vec<String> = failures.iter().map(|(kind, count)| format!("{} {}", kind, count)).collect();
+            lines.push(kinds.join(" "));
+        }
+        if let Some(latency) = metrics.histogram(metrics::API_LATENCY_MS) {
+            lines.push(format!("latency p50 {:.0} p95 {:.0} ms", latency.quantile(0.5), latency.quantile(0.95)));
+        }
+        if let Some(screenshots) = metrics.histogram(metrics::SCREENSHOT_BYTES) {
+            lines.push(format!("screenshot {:.0} KiB", screenshots.mean() / 1024.0));
+        }
+        let frame_ms = metrics.histogram(metrics::FRAME_MS).map_or(0.0, |frames| frames.quantile(0.95));
+        lines.push(format!("tts fail {} frame p95 {:.0} ms", metrics.counter(metrics::TTS_FAILURES), frame_ms));
+        if let Some((at, message)) = metrics.last_error() {
+            lines.push(format!("err {} {}", at.format("%H:%M:%S"), message));
+        }
+        
+        lines.into_iter().map(|line| truncate_line(line, DEBUG_LINE_CHARS)).collect()
+    }
+    
+    // Bảng chữ đơn cách nền tối, đặt vào chỗ thân khung chat
+    fn debug_panel(&self, layout: &BubbleLayout) -> Element<'_, Message> {
+        let lines = self.debug_lines().into_iter().fold(Column::new(), |column, line| {
+            column.push(Text::new(line).size(DEBUG_FONT_SIZE).font(Font::MONOSPACE))
+        });
+        let panel = Container::new(lines)
+            .padding(6)
+            .width(Length::Fixed(layout.body.width() as f32))
+            .height(Length::Fixed(layout.body.height() as f32))
+            .style(iced::theme::Container::Box);
+        
+        Container::new(placed(panel, layout.body.left - layout.canvas.left, layout.body.top - layout.canvas.top))
+            .width(Length::Fixed(layout.canvas.width() as f32))
+            .height(Length::Fixed(layout.canvas.height() as f32))
+            .into()
+    }
+}
+
+// Cắt dòng dài hơn `max` ký tự, thay phần cuối bằng "…"
+fn truncate_line(line: String, max: usize) -> String {
+    if line.chars().count() <= max {
+        return line;
+    }
+    let end = line.char_indices().nth(max - 1).map_or(line.len(), |(end, _)| end);
+    format!("{}…", &line[..end])
+}
+
+// Nạp bộ sprite, dùng khung trong suốt nếu thư mục lỗi
+fn load_sprites(dir: &Path) -> SpritePack {
+    match SpritePack::load(dir) {
+        Ok(pack) => {
+            info!("Loaded sprites from {}", dir.display());
+            pack
+        }
+        Err(e) => {
+            error!("Invalid sprite pack {}:\n{}\nUsing placeholder sprites", dir.display(), e);
+            SpritePack::placeholder()
+        }
+    }
+}
+
+// Nạp tiếng mèo của bộ sprite, im lặng nếu có lỗi
+fn load_sounds(dir: &Path) -> SoundPack {
+    SoundPack::load(dir).unwrap_or_else(|e| {
+        error!("Invalid sounds in {}:\n{}\nSounds disabled", dir.display(), e);
+        SoundPack::default()
+    })
+}
+
+// Where iced's Image (ContentFit::Contain) draws a `width`x`height` frame
+// inside `bounds`: scaled to fit, keeping the aspect ratio, centred
+pub fn sprite_rect(width: u32, height: u32, bounds: WindowRect) -> WindowRect {
+    let scale = (bounds.width() as f32 / width.max(1) as f32).min(bounds.height() as f32 / height.max(1) as f32);
+    let w = (width as f32 * scale).round() as i32;
+    let h = (height as f32 * scale).round() as i32;
+    let left = bounds.left + (bounds.width() - w) / 2;
+    let top = bounds.top + (bounds.height() - h) / 2;
+    WindowRect::new(left, top, left + w, top + h)
+}
+
+// Đẩy widget xuống/sang phải một khoảng (pixel) bằng padding
+fn placed<'a>(content: impl Into<Element<'a, Message>>, left: i32, top: i32) -> Element<'a, Message> {
+    Container::new(content)
+        .padding([top.max(0) as u16, 0, 0, left.max(0) as u16])
+        .into()
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing::FakeClock;
+    use crate::window_handler::{FakeWindowInfo, WindowRect};
+    
+    const FRAME: Duration = Duration::from_millis(16);
+    
+    fn controller_with(windows: Vec<WindowRect>) -> (SpriteController, Arc<FakeWindowInfo>, Arc<FakeClock>) {
+        let info = Arc::new(FakeWindowInfo::new(WindowRect::new(0, 0, 1280, 720)));
+        info.set_windows(windows);
+        let clock = Arc::new(FakeClock::new());
+        let mut config = Config::default();
+        config.display.sprite_dir = PathBuf::from("does-not-exist");
+        let mut controller = SpriteController::new(info.clone(), clock.clone(), &config, Some(1));
+        controller.refresh_ledges().unwrap();
+        (controller, info, clock)
+    }
+    
+    #[test]
+    fn test_ground_follows_work_area() {
+        let (controller, _, _) = controller_with(vec![]);
+        assert_eq!(controller.screen_width, 1280);
+        assert_eq!(controller.ground_y(), 720);
+    }
+    
+    #[test]
+    fn test_hit_region_follows_sprite_pixels() {
+        let (mut controller, _, _) = controller_with(vec![]);
+        
+        // Sprite trong suốt hoàn toàn: không nhận chuột
+        assert!(controller.hit_region().is_empty());
+        
+        // 50x50 sprite drawn at 100x100: opaque top-left quarter doubles in size
+        let mut frame = image::RgbaImage::new(50, 50);
+        for y in 0..10 {
+            for x in 0..10 {
+                frame.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+            }
+        }
+        controller
+            .animation_frames
+            .insert("idle_right".to_string(), vec![image::DynamicImage::ImageRgba8(frame)]);
+        
+        let cat_top = WINDOW_SIZE.1 as i32 - CAT_SIZE;
+        assert_eq!(
+            controller.hit_region(),
+            vec![WindowRect::new(0, cat_top, 20, cat_top + 20)]
+        );
+        
+        // Khung chat cũng nhận chuột
+        controller.show_chat("Meow!".to_string());
+        let region = controller.hit_region();
+        assert_eq!(region.len(), 2);
+        assert_eq!(region[1], controller.bubble_layout().canvas);
+    }
+    
+    #[test]
+    fn test_jump_onto_window_and_fall_when_it_closes() {
+        let (mut controller, info, clock) = controller_with(vec![WindowRect::new(200, 500, 800, 700)]);
+        controller.pos = Position { x: 120, y: 720 };
+        
+        assert!(controller.try_jump().unwrap());
+        clock.advance(JUMP_DURATION);
+        controller.handle_jumping(clock.now()).unwrap();
+        assert_eq!(controller.pos.y, 500);
+        assert!(controller.perch.is_some());
+        
+        // Đóng cửa sổ: mèo rơi xuống đất
+        info.set_windows(vec![]);
+        controller.refresh_ledges().unwrap();
+        assert!(matches!(controller.animation_state, AnimationState::Falling));
+        for _ in 0..100 {
+            controller.handle_falling(FRAME).unwrap();
+        }
+        assert_eq!(controller.pos.y, 720);
+        assert!(controller.perch.is_none());
+    }
+    
+    // Controller with the bundled sprites, for rendering
+    fn sprite_controller() -> (SpriteController, Arc<FakeClock>) {
+        let (controller, _, clock) = sprite_controller_with(&Config::default());
+        (controller, clock)
+    }
+    
+    // Same, with `config` and canned comments instead of API calls
+    fn sprite_controller_with(config: &Config) -> (SpriteController, Arc<FakeWindowInfo>, Arc<FakeClock>) {
+        let info = Arc::new(FakeWindowInfo::new(WindowRect::new(0, 0, 1280, 720)));
+        let clock = Arc::new(FakeClock::new());
+        let mut config = config.clone();
+        config.display.sprite_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites");
+        config.comments.provider = crate::config::Provider::Offline;
+        let controller = SpriteController::new(info.clone(), clock.clone(), &config, Some(1));
+        (controller, info, clock)
+    }
+    
+    #[test]
+    fn test_sprite_rect_keeps_aspect_ratio() {
+        let bounds = WindowRect::new(0, 200, 100, 300);
+        assert_eq!(sprite_rect(25, 20, bounds), WindowRect::new(0, 210, 100, 290));
+        assert_eq!(sprite_rect(50, 50, bounds), bounds);
+    }
+    
+    #[test]
+    fn test_render_matches_golden_images() {
+        // Đứng yên dưới đất, không có khung chat
+        let (mut controller, clock) = sprite_controller();
+        controller.pos = Position { x: 600, y: 720 };
+        controller.set_animation("idle_right").unwrap();
+        crate::render::assert_golden("idle", &controller.render());
+        
+        // Frame sau của animation đi sang trái
+        controller.set_animation("move_left").unwrap();
+        controller.frame_index = controller.max_frame_index.min(2);
+        crate::render::assert_golden("move_left", &controller.render());
+        
+        // Ngồi, khung chat phía trên đã hiện hết chữ
+        controller.set_animation("sitting").unwrap();
+        controller.show_chat("Meow! What are you working on? It looks interesting.".to_string());
+        clock.advance(Duration::from_secs(5));
+        crate::render::assert_golden("bubble_above", &controller.render());
+        
+        // Đang hiện dần từng chữ
+        controller.show_chat("Purr... this is taking a while".to_string());
+        clock.advance(Duration::from_millis(320));
+        crate::render::assert_golden("bubble_typing", &controller.render());
+        
+        // Mờ một nửa
+        clock.advance(Duration::from_secs(5));
+        controller.bubble.as_mut().unwrap().start_fade(clock.now());
+        clock.advance(Duration::from_millis(240));
+        crate::render::assert_golden("bubble_fading", &controller.render());
+        
+        // Sát mép trên: khung chat bên phải, sát thêm mép phải: bên trái
+        controller.pos = Position { x: 300, y: 120 };
+        controller.show_chat("Up here!".to_string());
+        clock.advance(Duration::from_secs(5));
+        crate::render::assert_golden("bubble_right", &controller.render());
+        
+        controller.pos = Position { x: 1180, y: 120 };
+        crate::render::assert_golden("bubble_left", &controller.render());
+    }
+    
+    #[test]
+    fn test_chat_stays_until_speech_finishes() {
+        let (mut controller, _, clock) = controller_with(vec![]);
+        let recording = speech::RecordingSpeech::default();
+        controller.speaker = Speaker::new(Box::new(recording.clone()), &Config::default());
+        controller.speaker.say("Meow!");
+        controller.show_chat("Meow!".to_string());
+        
+        // Hiển thị đủ lâu cho TTS, rồi mờ dần và ẩn đi
+        clock.advance(Duration::from_secs(10));
+        controller.handle_animation().unwrap();
+        assert!(controller.chat_visible);
+        
+        recording.finish();
+        clock.advance(Duration::from_millis(16));
+        controller.handle_animation().unwrap();
+        assert!(controller.chat_visible);
+        
+        clock.advance(Duration::from_secs(1));
+        controller.handle_animation().unwrap();
+        assert!(controller.chat_visible);
+        
+        clock.advance(Duration::from_secs(1));
+        controller.handle_animation().unwrap();
+        assert!(!controller.chat_visible);
+        assert!(controller.next_comment_at >= clock.now() + controller.comment_schedule.min);
+        
+        // Nạp lại cấu hình với max ngắn hơn: không phải chờ theo lịch cũ
+        let mut config = Config::default();
+        config.display.sprite_dir = controller.sprite_dir.clone();
+        config.comments.min_interval_secs = 10.0;
+        config.comments.average_interval_secs = 30.0;
+        config.comments.max_interval_secs = 60.0;
+        controller.next_comment_at = clock.now() + Duration::from_secs(7200);
+        controller.apply_config(&config);
+        assert_eq!(controller.next_comment_at, clock.now() + Duration::from_secs(60));
+    }
+    
+    #[test]
+    fn test_debug_overlay_takes_the_bubble_slot() {
+        let (mut controller, clock) = sprite_controller();
+        controller.show_chat("Meow! What are you doing? That looks interesting!".to_string());
+        let bubble_layout = controller.bubble_layout();
+        
+        controller.toggle_debug();
+        let layout = controller.bubble_layout();
+        assert_eq!(layout.body.width(), DEBUG_PANEL_SIZE.0);
+        assert_eq!(layout.body.height(), DEBUG_PANEL_SIZE.1);
+        assert_ne!(layout, bubble_layout);
+        
+        metrics::set_last_error("Failed to generate comment: timed out");
+        clock.advance(Duration::from_secs(1));
+        let lines = controller.debug_lines();
+        assert!(lines[0].starts_with(&format!("idle {} ", controller.current_animation)), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.starts_with("chat: Meow!")), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.starts_with("err ") && line.contains("Failed to")), "{:?}", lines);
+        assert!(lines.iter().all(|line| line.chars().count() <= DEBUG_LINE_CHARS));
+        
+        // Dòng vừa đủ dài thì giữ nguyên, dài hơn một ký tự thì cắt
+        let exact = "é".repeat(DEBUG_LINE_CHARS);
+        assert_eq!(truncate_line(exact.clone(), DEBUG_LINE_CHARS), exact);
+        let long = truncate_line(format!("{}x", exact), DEBUG_LINE_CHARS);
+        assert_eq!(long, format!("{}…", "é".repeat(DEBUG_LINE_CHARS - 1)));
+        
+        controller.toggle_debug();
+        assert_eq!(controller.bubble_layout(), bubble_layout);
+    }
+    
+    #[test]
+    fn test_landing_and_petting_play_sounds() {
+        let (mut controller, _, clock) = controller_with(vec![]);
+        let output = crate::audio::RecordingOutput::default();
+        let mut config = Config::default();
+        let pack = SoundPack::load(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites")).unwrap();
+        controller.sounds = SoundPlayer::new(pack, Box::new(output.clone()), &config, clock.clone());
+        
+        controller.pos.y = 400;
+        controller.start_fall().unwrap();
+        while matches!(controller.animation_state, AnimationState::Falling) {
+            controller.handle_falling(FRAME).unwrap();
+        }
+        assert_eq!(controller.pos.y, 720);
+        controller.pet();
+        assert_eq!(output.played(), vec![("thud".to_string(), 0.8), ("purr".to_string(), 0.8)]);
+        
+        // Giờ yên lặng: mèo vẫn chạy nhưng không kêu
+        config.quiet.hours = vec![crate::timing::TimeRange::try_from("11:00-13:00".to_string()).unwrap()];
+        controller.apply_config(&config);
+        controller.pet();
+        assert_eq!(output.played().len(), 2);
+        
+        // Tắt tiếng từ khay hệ thống: không kêu, không đọc
+        config.quiet.hours.clear();
+        controller.apply_config(&config);
+        let recording = speech::RecordingSpeech::default();
+        controller.speaker = Speaker::new(Box::new(recording.clone()), &config);
+        controller.set_muted(true);
+        controller.pet();
+        controller.speaker.say("Meow!");
+        assert_eq!(output.played().len(), 2);
+        assert!(recording.calls().iter().all(|call| !call.starts_with("speak")));
+        controller.set_muted(false);
+        controller.pet();
+        assert_eq!(output.played().len(), 3);
+    }
+    
+    #[test]
+    fn test_needs_drive_behavior() {
+        let mut needs = Needs::default();
+        needs.drift(Duration::from_secs(5 * 3600), false);
+        assert_eq!(needs.mood(), Mood::Hungry);
+        needs.feed();
+        assert!(needs.hunger < HUNGRY && needs.energy < SLEEPY);
+        assert_eq!(needs.mood(), Mood::Sleepy);
+        
+        // Mệt thì ngủ tới khi đủ năng lượng
+        let (mut controller, _, clock) = controller_with(vec![]);
+        controller.next_comment_at = clock.now() + Duration::from_secs(24 * 3600);
+        controller.needs.energy = TIRED - 0.01;
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert!(matches!(controller.animation_state, AnimationState::Sleeping));
+        controller.needs.energy = RESTED;
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert!(matches!(controller.animation_state, AnimationState::Idle));
+        
+        // Chán thì chạy nhanh hơn đi bộ cho tới khi đỡ chán
+        controller.needs.boredom = BORED;
+        controller.next_step_at = clock.now();
+        controller.handle_animation().unwrap();
+        assert!(controller.running);
+        assert_eq!(controller.current_animation, "move_right");
+        assert_eq!(controller.next_step_at - clock.now(), controller.movement.step_interval() / RUN_SPEEDUP);
+        while controller.running {
+            clock.advance(controller.next_step_at - clock.now());
+            controller.handle_animation().unwrap();
+        }
+        assert!(controller.needs.boredom < ENTERTAINED);
+    }
+    
+    #[test]
+    fn test_fullscreen_and_snooze_put_the_cat_to_sleep() {
+        let config = Config::default();
+        let (mut controller, info, clock) = sprite_controller_with(&config);
+        let recording = speech::RecordingSpeech::default();
+        controller.speaker = Speaker::new(Box::new(recording.clone()), &config);
+        controller.speaker.say("Meow!");
+        controller.show_chat("Meow!".to_string());
+        
+        info.set_foreground(Some(crate::window_handler::ForegroundWindow {
+            title: "Slides".to_string(),
+            app: "soffice".to_string(),
+            rect: WindowRect::new(0, 0, 1280, 720),
+            fullscreen: true,
+        }));
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert_eq!(controller.quiet_reason, Some("fullscreen window"));
+        assert_eq!(recording.calls().last().unwrap(), "stop");
+        
+        // Bình luận tới hạn trong lúc yên lặng bị bỏ qua, mèo đi ngủ
+        controller.next_comment_at = clock.now();
+        for _ in 0..300 {
+            clock.advance(FRAME);
+            controller.handle_animation().unwrap();
+        }
+        assert!(!controller.chat_visible);
+        assert!(matches!(controller.animation_state, AnimationState::Sleeping));
+        assert_eq!(controller.current_animation, "sleeping_1");
+        
+        info.set_foreground(None);
+        clock.advance(LEDGE_REFRESH_INTERVAL);
+        controller.handle_animation().unwrap();
+        assert!(matches!(controller.animation_state, AnimationState::Idle));
+        assert!(controller.next_comment_at >= clock.now() + controller.comment_schedule.min);
+        assert_eq!(recording.calls().iter().filter(|call| call.starts_with("speak")).count(), 1);
+        
+        controller.toggle_snooze();
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert_eq!(controller.quiet_reason, Some("snoozed"));
+        controller.toggle_snooze();
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert_eq!(controller.quiet_reason, None);
+    }
+    
+    #[test]
+    fn test_control_commands_sleep_wake_and_say() {
+        let config = Config::default();
+        let (mut controller, _, clock) = sprite_controller_with(&config);
+        let recording = speech::RecordingSpeech::default();
+        controller.speaker = Speaker::new(Box::new(recording.clone()), &config);
+        
+        // Lệnh điều khiển: ngủ thì không nói, thức dậy thì nói được
+        controller.sleep();
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        assert!(controller.say("Build finished").unwrap_err().to_string().contains("snoozed"));
+        controller.wake().unwrap();
+        clock.advance(FRAME);
+        controller.handle_animation().unwrap();
+        controller.say("Build finished").unwrap();
+        assert_eq!(controller.chat_response.as_deref(), Some("Build finished"));
+        assert_eq!(recording.calls().last().unwrap(), "speak Build finished");
+        assert!(controller.play("no_such_animation").is_err());
+        controller.play("sitting").unwrap();
+        assert_eq!(controller.current_animation, "sitting");
+    }
+    
+    #[test]
+    fn test_finished_commands_make_the_cat_react() {
+        let config = Config::default();
+        let (mut controller, _, clock) = sprite_controller_with(&config);
+        let recording = speech::RecordingSpeech::default();
+        controller.speaker = Speaker::new(Box::new(recording.clone()), &config);
+        
+        // Lệnh chạy xong: ngã ngửa một lần, nói câu có tên lệnh, rồi ngồi
+        controller.react_to_command("cargo test", Some(101)).unwrap();
+        assert_eq!(controller.current_animation, "fall");
+        let comment = controller.chat_response.clone().unwrap();
+        assert!(comment.contains("\"cargo test\"") && comment.contains("exit code 101"), "{}", comment);
+        assert_eq!(recording.calls().last().unwrap(), &format!("speak {}", comment));
+        clock.advance(controller.animation_duration("fall") + FRAME);
+        controller.handle_animation().unwrap();
+        assert_eq!(controller.current_animation, "sitting");
+        
+        // Thành công thì nhảy mừng
+        controller.react_to_command("cargo build", Some(0)).unwrap();
+        assert_eq!(controller.current_animation, "jump_right");
+        assert!(controller.chat_response.as_deref().unwrap().contains("\"cargo build\""));
+    }
+    
+    #[test]
+    fn test_window_events_trigger_comments() {
+        let mut config = Config::default();
+        config.privacy.deny_titles = vec!["Bank".to_string()];
+        let (mut controller, info, clock) = sprite_controller_with(&config);
+        controller.next_comment_at = clock.now() + Duration::from_secs(24 * 3600);
+        let focus = |app: &str, title: &str| {
+            info.set_foreground(Some(ForegroundWindow {
+                title: title.to_string(),
+                app: app.to_string(),
+                rect: WindowRect::new(0, 0, 800, 600),
+                fullscreen: false,
+            }));
+        };
+        
+        focus("code", "main.rs");
+        clock.advance(controller.comment_schedule.min);
+        controller.handle_animation().unwrap();
+        assert!(!controller.chat_visible);
+        
+        // Đổi ứng dụng: bình luận ngay, không chờ lịch
+        focus("firefox", "Docs");
+        clock.advance(LEDGE_REFRESH_INTERVAL);
+        controller.handle_animation().unwrap();
+        assert!(controller.chat_visible);
+        assert!(controller.pending_event.is_none());
+        assert!(metrics::snapshot().counter("comments.events.app_switch") >= 1);
+        
+        // Cửa sổ riêng tư không gây ra sự kiện
+        focus("firefox", "My Bank - Login");
+        clock.advance(LEDGE_REFRESH_INTERVAL);
+        controller.handle_animation().unwrap();
+        assert!(controller.pending_event.is_none());
+        
+        // Rời máy trong lúc mèo đang nói: sự kiện chờ tới khi chat tắt
+        info.set_idle(Some(config.events.idle()));
+        clock.advance(LEDGE_REFRESH_INTERVAL);
+        controller.handle_animation().unwrap();
+        assert_eq!(controller.pending_event.as_ref().map(|(event, _)| event.kind().name()), Some("idle"));
+    }
+    
+    #[test]
+    fn test_comments_are_generated_in_the_background() {
+        // Server nhận request nhưng không bao giờ trả lời
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = Config::default();
+        config.comments.api_key = Some("test-key".to_string());
+        config.comments.api_base_url = Some(format!("http://{}", listener.local_addr().unwrap()));
+        config.comments.tts = false;
+        let online = || {
+            let (mut controller, _, clock) = controller_with(vec![]);
+            controller.commenter =
+                Commenter::new(&config.comments, Some(1)).with_screen_source(|| Ok(image::RgbaImage::new(4, 4)));
+            controller.comment_now().unwrap();
+            clock.advance(FRAME);
+            controller.handle_animation().unwrap();
+            (controller, clock)
+        };
+        
+        // Mèo vẫn chạy trong lúc chờ, câu trả lời tới sau qua comment_ready
+        let (mut controller, clock) = online();
+        assert_eq!(controller.chat_response.as_deref(), Some("Thinking..."));
+        let _response = controller.take_comment_job().unwrap();
+        let _request = listener.accept().unwrap();
+        let thinking = controller.render();
+        clock.advance(Duration::from_secs(30));
+        controller.handle_animation().unwrap();
+        assert!(controller.take_comment_job().is_none());
+        assert!(controller.bubble.as_ref().is_some_and(|bubble| !bubble.is_faded(clock.now())));
+        assert_ne!(controller.render(), thinking, "the bubble should finish typing \"Thinking...\"");
+        controller.comment_ready(Some("Purr, a blank screen.".to_string()));
+        assert_eq!(controller.chat_response.as_deref(), Some("Purr, a blank screen."));
+        
+        // Thoát giữa chừng: không chờ server, bỏ câu trả lời
+        let (mut controller, _) = online();
+        let response = controller.take_comment_job().unwrap();
+        let _request = listener.accept().unwrap();
+        controller.shutdown();
+        let started = Instant::now();
+        assert_eq!(iced::futures::executor::block_on(response), None);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        controller.comment_ready(None);
+        assert!(!controller.chat_visible);
+    }
+    
+    #[test]
+    fn test_restore_state_after_a_weekend() {
+        let (mut controller, _, clock) = controller_with(vec![]);
+        controller.pos = Position { x: 5000, y: 400 };
+        controller.direction = Direction::Left;
+        controller.last_comment_at = Some(clock.local_time());
+        let mut state = controller.state();
+        
+        // Vừa mới bình luận: chưa bình luận lại ngay
+        let (mut restored, _, clock) = controller_with(vec![WindowRect::new(200, 500, 800, 700)]);
+        restored.restore(&state).unwrap();
+        assert_eq!(restored.pos.x, 1280 - CAT_SIZE);
+        assert!(restored.next_comment_at - clock.now() >= restored.comment_schedule.min);
+        assert!(matches!(restored.animation_state, AnimationState::Falling));
+        
+        // Hai ngày ở một mình: ngủ đủ nhưng đói và cô đơn
+        state.saved_at -= chrono::Duration::days(2);
+        let (mut restored, _, _) = controller_with(vec![]);
+        restored.restore(&state).unwrap();
+        assert_eq!(restored.direction, Direction::Left);
+        assert_eq!(restored.needs.energy, 1.0);
+        assert_eq!(restored.needs.mood(), Mood::Hungry);
+        assert!(restored.needs.affection < LONELY);
+    }
+}
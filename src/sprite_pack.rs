@@ -0,0 +1,179 @@
+// Bộ sprite: mỗi animation là một thư mục con chứa các frame (png, gif...),
+// sắp theo tên file. Tên file dạng "frame_00_delay-0.25s.gif" cho biết
+// thời gian hiển thị frame.
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+// Các animation mà SpriteController cần
+pub const REQUIRED_ANIMATIONS: [&str; 8] = [
+    "idle_right",
+    "idle_left",
+    "sitting",
+    "move_right",
+    "move_left",
+    "jump_right",
+    "jump_left",
+    "fall",
+];
+
+pub const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default)]
+pub struct SpritePack {
+    pub frames: HashMap<String, Vec<DynamicImage>>,
+    pub delays: HashMap<String, Vec<Duration>>,
+}
+
+impl SpritePack {
+    // Khung trong suốt dùng khi không nạp được bộ sprite
+    pub fn placeholder() -> Self {
+        let mut pack = Self::default();
+        for name in REQUIRED_ANIMATIONS {
+            pack.frames.insert(name.to_string(), vec![DynamicImage::new_rgba8(100, 100)]);
+            pack.delays.insert(name.to_string(), vec![DEFAULT_FRAME_DELAY]);
+        }
+        pack
+    }
+
+    // Loads every animation under `dir` and reports all problems at once:
+    // missing animations, empty folders, unreadable frames and frames whose
+    // size differs from the rest of their animation
+    pub fn load(dir: &Path) -> Result<Self> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| anyhow!("Cannot read sprite directory {}: {}", dir.display(), e))?;
+
+        let mut pack = Self::default();
+        let mut problems = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Tiếng mèo nằm cạnh các animation, xem sounds.rs
+            if name == crate::sounds::SOUNDS_DIR {
+                continue;
+            }
+
+            let mut files: Vec<_> = std::fs::read_dir(&path)
+                .map(|files| files.flatten().map(|f| f.path()).filter(|p| p.is_file()).collect())
+                .unwrap_or_default();
+            files.sort();
+
+            let mut frames = Vec::new();
+            let mut delays = Vec::new();
+            for file in files {
+                match image::open(&file) {
+                    Ok(frame) => {
+                        if let Some(first) = frames.first().map(|f: &DynamicImage| (f.width(), f.height())) {
+                            if first != (frame.width(), frame.height()) {
+                                problems.push(format!(
+                                    "{}: {}x{} frame {} differs from {}x{}",
+                                    name,
+                                    frame.width(),
+                                    frame.height(),
+                                    file.display(),
+                                    first.0,
+                                    first.1
+                                ));
+                            }
+                        }
+                        delays.push(frame_delay(&file).unwrap_or(DEFAULT_FRAME_DELAY));
+                        frames.push(frame);
+                    }
+                    Err(e) => problems.push(format!("{}: cannot decode {}: {}", name, file.display(), e)),
+                }
+            }
+
+            if frames.is_empty() {
+                problems.push(format!("{}: no frames", name));
+                continue;
+            }
+            pack.frames.insert(name.clone(), frames);
+            pack.delays.insert(name, delays);
+        }
+
+        for name in REQUIRED_ANIMATIONS {
+            if !pack.frames.contains_key(name) && !problems.iter().any(|p| p.starts_with(&format!("{}:", name))) {
+                problems.push(format!("{}: missing animation", name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(pack)
+        } else {
+            problems.sort();
+            Err(anyhow!("{}", problems.join("\n")))
+        }
+    }
+
+    // Mô tả ngắn từng animation, dùng cho validate-sprites
+    pub fn summary(&self) -> Vec<String> {
+        let mut names: Vec<&String> = self.frames.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let frames = &self.frames[name];
+                let total: Duration = self.delays[name].iter().sum();
+                format!(
+                    "{}: {} frame(s), {}x{}, {:.2}s",
+                    name,
+                    frames.len(),
+                    frames[0].width(),
+                    frames[0].height(),
+                    total.as_secs_f32()
+                )
+            })
+            .collect()
+    }
+}
+
+// "frame_03_delay-0.25s.gif" -> 250ms
+fn frame_delay(path: &Path) -> Option<Duration> {
+    let stem = path.file_stem()?.to_str()?;
+    let delay = stem.split("delay-").nth(1)?;
+    let seconds: f64 = delay.trim_end_matches('s').parse().ok()?;
+    (seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_sprites_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("sprites");
+        let pack = SpritePack::load(&dir).unwrap();
+        for name in REQUIRED_ANIMATIONS {
+            assert!(!pack.frames[name].is_empty(), "{}", name);
+        }
+        assert_eq!(pack.delays["idle_right"][0], Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_invalid_pack_reports_problems() {
+        let dir = std::env::temp_dir().join(format!("pycatai-sprites-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("idle_right")).unwrap();
+        std::fs::create_dir_all(dir.join("sitting")).unwrap();
+        image::RgbaImage::new(10, 10).save(dir.join("idle_right/a.png")).unwrap();
+        image::RgbaImage::new(20, 10).save(dir.join("idle_right/b.png")).unwrap();
+
+        let error = SpritePack::load(&dir).unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.contains("idle_right: 20x10 frame"));
+        assert!(error.contains("sitting: no frames"));
+        assert!(error.contains("fall: missing animation"));
+    }
+
+    #[test]
+    fn test_frame_delay_from_file_name() {
+        assert_eq!(frame_delay(Path::new("frame_0_delay-0.1s.gif")), Some(Duration::from_millis(100)));
+        assert_eq!(frame_delay(Path::new("tile000.png")), None);
+    }
+}
@@ -0,0 +1,122 @@
+// Trạng thái của mèo giữa các lần chạy: vị trí, hướng, nhu cầu, lần bình
+// luận cuối, persona và bộ sprite đang dùng. Lưu ra state.json cạnh
+// config.toml khi thoát và định kỳ trong lúc chạy.
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::comment_generator;
+use crate::config::Config;
+use crate::sprite_handler::{Direction, Needs};
+
+const STATE_FILE: &str = "state.json";
+
+// Lưu định kỳ để không mất nhiều khi chương trình bị tắt đột ngột
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PetState {
+    // Giờ máy lúc lưu, để tính thời gian mèo ở một mình
+    pub saved_at: NaiveDateTime,
+    pub x: i32,
+    pub y: i32,
+    pub direction: Direction,
+    pub needs: Needs,
+    pub last_comment_at: Option<NaiveDateTime>,
+    pub persona: String,
+    pub skin: PathBuf,
+}
+
+impl PetState {
+    // state.json next to the config file, so each config has its own cat
+    pub fn path_beside(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(STATE_FILE)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid state file {}", path.display()))
+    }
+
+    // Ghi ra file tạm rồi đổi tên, để không bao giờ để lại file lưu dở
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    // Thời gian từ lần lưu tới `now`; đồng hồ máy bị chỉnh lùi thì coi là 0
+    pub fn offline_for(&self, now: NaiveDateTime) -> Duration {
+        (now - self.saved_at).to_std().unwrap_or_default()
+    }
+
+    // Persona and sprite pack chosen while running win over config.toml,
+    // unless the file was edited after the state was saved
+    pub fn restore_settings(&self, config: &mut Config, config_modified: Option<SystemTime>) {
+        let saved_at = self.saved_at.and_local_timezone(chrono::Local).earliest().map(SystemTime::from);
+        if let (Some(modified), Some(saved_at)) = (config_modified, saved_at) {
+            if modified > saved_at {
+                return;
+            }
+        }
+        // Persona file có thể đã bị xoá
+        if comment_generator::persona_prompt(&self.persona).is_ok() && config.comments.persona != self.persona {
+            info!("Restoring persona {}", self.persona);
+            config.comments.persona = self.persona.clone();
+        }
+        if self.skin.is_dir() && config.display.sprite_dir != self.skin {
+            info!("Restoring sprites {}", self.skin.display());
+            config.display.sprite_dir = self.skin.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> PetState {
+        PetState {
+            saved_at: NaiveDateTime::parse_from_str("2024-01-05 18:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+            x: 640,
+            y: 500,
+            direction: Direction::Left,
+            needs: Needs::default(),
+            last_comment_at: None,
+            persona: "kitten".to_string(),
+            skin: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites"),
+        }
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let path = std::env::temp_dir().join(format!("pycatai-state-{}.json", std::process::id()));
+        state().save(&path).unwrap();
+        let loaded = PetState::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, state());
+
+        let monday = NaiveDateTime::parse_from_str("2024-01-08 09:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(loaded.offline_for(monday), Duration::from_secs(63 * 3600));
+        assert_eq!(loaded.offline_for(state().saved_at - chrono::Duration::hours(1)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_newer_config_file_wins() {
+        let mut config = Config::default();
+        state().restore_settings(&mut config, Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(config.comments.persona, "kitten");
+        assert_eq!(config.display.sprite_dir, state().skin);
+
+        let mut config = Config::default();
+        state().restore_settings(&mut config, Some(SystemTime::now()));
+        assert_eq!(config.comments.persona, Config::default().comments.persona);
+    }
+}
@@ -0,0 +1,214 @@
+// Đồng hồ và lịch bình luận: mọi bộ đếm thời gian của mèo tính bằng
+// Duration thay vì số tick, để hành vi không phụ thuộc tốc độ khung hình
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// Chat hiển thị ít nhất/nhiều nhất bao lâu (khi không có TTS)
+const CHAT_MIN_DURATION: Duration = Duration::from_secs(4);
+const CHAT_MAX_DURATION: Duration = Duration::from_secs(30);
+const CHAT_BASE_DURATION: Duration = Duration::from_secs(2);
+const CHAT_READ_PER_CHAR: Duration = Duration::from_millis(60);
+// Giữ khung chat thêm một chút sau khi TTS đọc xong
+const CHAT_AFTER_SPEECH: Duration = Duration::from_secs(1);
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    // Giờ địa phương, cho các khung giờ yên lặng
+    fn local_time(&self) -> NaiveDateTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local_time(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+// Đồng hồ giả cho test và chạy mô phỏng: chỉ chạy khi gọi advance()
+pub struct FakeClock {
+    start: Instant,
+    start_local: NaiveDateTime,
+    elapsed: std::sync::Mutex<Duration>,
+}
+
+impl FakeClock {
+    // Starts at noon on a fixed day so runs do not depend on the real time
+    pub fn new() -> Self {
+        Self::starting_at(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap())
+    }
+
+    pub fn starting_at(local: NaiveDateTime) -> Self {
+        Self {
+            start: Instant::now(),
+            start_local: local,
+            elapsed: std::sync::Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn local_time(&self) -> NaiveDateTime {
+        let elapsed = chrono::Duration::from_std(*self.elapsed.lock().unwrap()).unwrap_or(chrono::Duration::zero());
+        self.start_local + elapsed
+    }
+}
+
+// Khoảng giờ trong ngày dạng "22:00-07:00", có thể qua nửa đêm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeRange {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeRange {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl TryFrom<String> for TimeRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let parse = |part: &str| {
+            NaiveTime::parse_from_str(part.trim(), "%H:%M")
+                .map_err(|_| format!("invalid time range '{}', expected HH:MM-HH:MM", value))
+        };
+        let (start, end) = value
+            .split_once('-')
+            .ok_or_else(|| format!("invalid time range '{}', expected HH:MM-HH:MM", value))?;
+        Ok(Self { start: parse(start)?, end: parse(end)? })
+    }
+}
+
+impl From<TimeRange> for String {
+    fn from(range: TimeRange) -> Self {
+        format!("{}-{}", range.start.format("%H:%M"), range.end.format("%H:%M"))
+    }
+}
+
+// Tần suất bình luận: trung bình `average`, cách nhau ít nhất `min` và
+// nhiều nhất `max`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommentSchedule {
+    pub average: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Default for CommentSchedule {
+    fn default() -> Self {
+        Self {
+            average: Duration::from_secs(60),
+            min: Duration::from_secs(20),
+            max: Duration::from_secs(300),
+        }
+    }
+}
+
+impl CommentSchedule {
+    // Random spacing until the next comment: `min` plus an exponential wait,
+    // so comments feel spontaneous but still average out to `average`
+    pub fn next_delay(&self, rng: &mut impl Rng) -> Duration {
+        let max = self.max.max(self.min);
+        let mean = self.average.clamp(self.min, max) - self.min;
+        let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+        (self.min + mean.mul_f64(-u.ln())).min(max)
+    }
+}
+
+// Bộ sinh số ngẫu nhiên; cùng seed cho cùng chuỗi hành vi
+pub fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+// Thời gian hiển thị chat: đủ để đọc hết câu, và không tắt trước khi TTS đọc xong
+pub fn chat_duration(text: &str, speech: Option<Duration>) -> Duration {
+    let reading = CHAT_BASE_DURATION + CHAT_READ_PER_CHAR * text.chars().count() as u32;
+    let reading = reading.clamp(CHAT_MIN_DURATION, CHAT_MAX_DURATION);
+
+    match speech {
+        Some(speech) => reading.max(speech + CHAT_AFTER_SPEECH),
+        None => reading,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_schedule_spacing() {
+        let schedule = CommentSchedule {
+            average: Duration::from_secs(120),
+            min: Duration::from_secs(30),
+            max: Duration::from_secs(600),
+        };
+        let mut rng = rng(Some(7));
+
+        let delays: Vec<Duration> = (0..2000).map(|_| schedule.next_delay(&mut rng)).collect();
+        assert!(delays.iter().all(|d| *d >= schedule.min && *d <= schedule.max));
+
+        // Trung bình gần với average (bị kéo xuống một chút vì kẹp max)
+        let mean = delays.iter().sum::<Duration>() / delays.len() as u32;
+        assert!(mean > Duration::from_secs(100) && mean < Duration::from_secs(130), "{:?}", mean);
+    }
+
+    #[test]
+    fn test_chat_duration_scales_with_text_and_speech() {
+        let short = chat_duration("Meow!", None);
+        let long = chat_duration(&"meow ".repeat(40), None);
+        assert_eq!(short, CHAT_MIN_DURATION);
+        assert!(long > short && long <= CHAT_MAX_DURATION);
+
+        // TTS dài hơn thời gian đọc: chờ đọc xong
+        let spoken = chat_duration("Meow!", Some(Duration::from_secs(45)));
+        assert_eq!(spoken, Duration::from_secs(46));
+    }
+
+    #[test]
+    fn test_fake_clock() {
+        let clock = FakeClock::new();
+        let start = clock.now();
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now() - start, Duration::from_millis(1500));
+        assert_eq!(clock.local_time().time(), NaiveTime::from_hms_milli_opt(12, 0, 1, 500).unwrap());
+    }
+
+    #[test]
+    fn test_time_range_across_midnight() {
+        let night = TimeRange::try_from("22:00-07:00".to_string()).unwrap();
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert!(night.contains(at(23, 30)) && night.contains(at(6, 59)));
+        assert!(!night.contains(at(7, 0)) && !night.contains(at(12, 0)));
+        assert_eq!(String::from(night), "22:00-07:00");
+
+        let lunch = TimeRange::try_from("12:00 - 13:00".to_string()).unwrap();
+        assert!(lunch.contains(at(12, 30)) && !lunch.contains(at(13, 30)));
+        assert!(TimeRange::try_from("25:00-07:00".to_string()).is_err());
+    }
+}
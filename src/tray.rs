@@ -0,0 +1,155 @@
+// Biểu tượng ở khay hệ thống: menu tạm dừng, overlay, bình luận ngay, tắt
+// tiếng, đổi persona, đổi bộ sprite, cài đặt và thoát. Backend chạy trên
+// luồng riêng, gửi lựa chọn qua kênh để Display đọc mỗi lần kiểm tra.
+use log::warn;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::sprite_pack::SpritePack;
+
+// Thư mục chứa các bộ sprite khác, nằm cạnh config.toml
+pub const SKINS_DIR: &str = "skins";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayAction {
+    TogglePause,
+    ToggleOverlay,
+    CommentNow,
+    ToggleMute,
+    Persona(String),
+    Skin(PathBuf),
+    Settings,
+    Quit,
+}
+
+// Những gì menu cần hiển thị
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TrayMenu {
+    pub paused: bool,
+    // Cửa sổ trong suốt, chuột bấm xuyên qua: phím O khó dùng vì cửa sổ
+    // hiếm khi được focus, nên menu cũng bật/tắt được
+    pub overlay: bool,
+    pub muted: bool,
+    pub persona: String,
+    pub personas: Vec<String>,
+    pub skin: PathBuf,
+    pub skins: Vec<PathBuf>,
+}
+
+impl TrayMenu {
+    pub fn selected_persona(&self) -> usize {
+        self.personas.iter().position(|name| *name == self.persona).unwrap_or(0)
+    }
+
+    pub fn selected_skin(&self) -> usize {
+        self.skins.iter().position(|dir| *dir == self.skin).unwrap_or(0)
+    }
+}
+
+pub trait TrayBackend: Send + Sync + Debug {
+    // Lựa chọn tiếp theo trong menu, nếu có
+    fn poll(&self) -> Option<TrayAction>;
+    fn update(&self, menu: &TrayMenu);
+}
+
+// Bộ sprite đang dùng và mọi bộ hợp lệ trong <config dir>/skins
+pub fn skins(current: &Path, skins_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut skins = vec![current.to_path_buf()];
+    let Some(entries) = skins_dir.and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return skins;
+    };
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir() && *path != current)
+        .filter(|path| match SpritePack::load(path) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Skipping sprite pack {}: {}", path.display(), e);
+                false
+            }
+        })
+        .collect();
+    found.sort();
+    skins.extend(found);
+    skins
+}
+
+pub fn skin_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+// Khung đầu tiên của animation đứng yên, làm ảnh xem trước của bộ sprite
+pub fn sprite_icon(dir: &Path) -> Option<image::RgbaImage> {
+    let pack = SpritePack::load(dir).ok()?;
+    Some(pack.frames.get("idle_right").and_then(|frames| frames.first())?.to_rgba8())
+}
+
+// assets/icon.ico dạng RGBA, dùng chung cho mọi backend; không đọc được thì
+// dùng con mèo trong bộ sprite đang chạy
+#[cfg(any(windows, target_os = "linux"))]
+fn icon_rgba(sprite_dir: &Path) -> Option<image::RgbaImage> {
+    match image::load_from_memory(include_bytes!("../assets/icon.ico")) {
+        Ok(icon) => Some(icon.to_rgba8()),
+        Err(e) => {
+            warn!("Failed to decode the tray icon, using the sprite pack instead: {}", e);
+            sprite_icon(sprite_dir)
+        }
+    }
+}
+
+pub fn spawn(menu: TrayMenu) -> Option<Arc<dyn TrayBackend>> {
+    #[cfg(target_os = "linux")]
+    {
+        let icon = icon_rgba(&menu.skin);
+        Some(Arc::new(crate::tray_ksni::KsniTray::spawn(menu, icon)))
+    }
+
+    #[cfg(windows)]
+    {
+        let icon = icon_rgba(&menu.skin);
+        Some(Arc::new(crate::tray_win32::Win32Tray::spawn(menu, icon)))
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = menu;
+        warn!("No tray icon on this platform");
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skins_lists_valid_packs_after_the_current_one() {
+        let sprites = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites");
+        let dir = std::env::temp_dir().join(format!("pycatai-skins-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+
+        assert_eq!(skins(&sprites, Some(&dir)), vec![sprites.clone()]);
+        assert_eq!(skins(&sprites, Some(&dir.join("missing"))), vec![sprites.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let menu = TrayMenu {
+            skin: sprites.clone(),
+            skins: vec![PathBuf::from("other"), sprites],
+            ..Default::default()
+        };
+        assert_eq!(menu.selected_skin(), 1);
+        assert_eq!(skin_name(&menu.skins[0]), "other");
+    }
+
+    #[test]
+    fn test_tray_icon_decodes() {
+        let sprites = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sprites");
+        let icon = icon_rgba(&PathBuf::from("does-not-exist")).unwrap();
+        assert!(icon.width() >= 16 && icon.height() >= 16);
+        assert!(icon.pixels().any(|pixel| pixel[3] > 0));
+        assert!(sprite_icon(&sprites).is_some());
+    }
+}
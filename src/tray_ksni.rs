@@ -0,0 +1,194 @@
+// Backend khay hệ thống cho Linux: StatusNotifierItem qua D-Bus
+use ksni::menu::{CheckmarkItem, RadioGroup, RadioItem, StandardItem, SubMenu};
+use ksni::{MenuItem, TrayMethods};
+use log::{error, info};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::tray::{self, TrayAction, TrayBackend, TrayMenu};
+
+#[derive(Debug)]
+pub struct KsniTray {
+    actions: Mutex<Receiver<TrayAction>>,
+    updates: UnboundedSender<TrayMenu>,
+}
+
+impl KsniTray {
+    pub fn spawn(menu: TrayMenu, icon: Option<image::RgbaImage>) -> Self {
+        let (action_sender, actions) = mpsc::channel();
+        let (updates, update_receiver) = unbounded_channel();
+        let item = CatTray {
+            menu,
+            icon: icon.map(to_argb).into_iter().collect(),
+            actions: action_sender,
+        };
+        // ksni cần runtime bất đồng bộ riêng, không phụ thuộc vào iced
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    error!("Failed to start the tray runtime: {}", e);
+                    return;
+                }
+            };
+            runtime.block_on(serve(item, update_receiver));
+        });
+        Self {
+            actions: Mutex::new(actions),
+            updates,
+        }
+    }
+}
+
+impl TrayBackend for KsniTray {
+    fn poll(&self) -> Option<TrayAction> {
+        self.actions.lock().ok()?.try_recv().ok()
+    }
+
+    fn update(&self, menu: &TrayMenu) {
+        // The tray thread may have given up, nothing to update then
+        let _ = self.updates.send(menu.clone());
+    }
+}
+
+async fn serve(item: CatTray, mut updates: UnboundedReceiver<TrayMenu>) {
+    let handle = match item.spawn().await {
+        Ok(handle) => handle,
+        Err(e) => {
+            error!("Tray icon unavailable: {}", e);
+            return;
+        }
+    };
+    info!("Tray icon ready");
+    while let Some(menu) = updates.recv().await {
+        handle.update(|item: &mut CatTray| item.menu = menu).await;
+    }
+}
+
+#[derive(Debug)]
+struct CatTray {
+    menu: TrayMenu,
+    icon: Vec<ksni::Icon>,
+    actions: Sender<TrayAction>,
+}
+
+impl CatTray {
+    fn send(&self, action: TrayAction) {
+        let _ = self.actions.send(action);
+    }
+}
+
+impl ksni::Tray for CatTray {
+    fn id(&self) -> String {
+        env!("CARGO_PKG_NAME").into()
+    }
+
+    fn title(&self) -> String {
+        "pyCatAI-pet".into()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.icon.clone()
+    }
+
+    // Không có ảnh thì dùng biểu tượng của theme, để mục trên khay không trống
+    fn icon_name(&self) -> String {
+        if self.icon.is_empty() {
+            "face-smile".into()
+        } else {
+            String::new()
+        }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let personas = RadioGroup {
+            selected: self.menu.selected_persona(),
+            select: Box::new(|this: &mut Self, index| {
+                if let Some(name) = this.menu.personas.get(index) {
+                    this.send(TrayAction::Persona(name.clone()));
+                }
+            }),
+            options: self
+                .menu
+                .personas
+                .iter()
+                .map(|name| RadioItem { label: name.clone(), ..Default::default() })
+                .collect(),
+        };
+        let skins = RadioGroup {
+            selected: self.menu.selected_skin(),
+            select: Box::new(|this: &mut Self, index| {
+                if let Some(dir) = this.menu.skins.get(index) {
+                    this.send(TrayAction::Skin(dir.clone()));
+                }
+            }),
+            options: self
+                .menu
+                .skins
+                .iter()
+                .map(|dir| RadioItem { label: tray::skin_name(dir), ..Default::default() })
+                .collect(),
+        };
+
+        vec![
+            CheckmarkItem {
+                label: "Pause".into(),
+                checked: self.menu.paused,
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::TogglePause)),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: "Overlay".into(),
+                checked: self.menu.overlay,
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::ToggleOverlay)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: "Comment now".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::CommentNow)),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: "Mute".into(),
+                checked: self.menu.muted,
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::ToggleMute)),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu { label: "Persona".into(), submenu: vec![personas.into()], ..Default::default() }.into(),
+            SubMenu { label: "Skin".into(), submenu: vec![skins.into()], ..Default::default() }.into(),
+            StandardItem {
+                label: "Settings...".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Settings)),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Quit".into(),
+                icon_name: "application-exit".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+}
+
+// ksni muốn ARGB32 theo thứ tự byte mạng
+fn to_argb(icon: image::RgbaImage) -> ksni::Icon {
+    let (width, height) = icon.dimensions();
+    let data = icon.pixels().flat_map(|pixel| {
+        let [r, g, b, a] = pixel.0;
+        [a, r, g, b]
+    });
+    ksni::Icon {
+        width: width as i32,
+        height: height as i32,
+        data: data.collect(),
+    }
+}
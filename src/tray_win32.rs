@@ -0,0 +1,140 @@
+// Backend khay hệ thống cho Windows: notification area qua tray-icon. Biểu
+// tượng cần vòng lặp message Win32 trên chính luồng đã tạo ra nó.
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tray_icon::menu::{CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+
+use crate::tray::{self, TrayAction, TrayBackend, TrayMenu};
+
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+pub struct Win32Tray {
+    actions: Mutex<Receiver<TrayAction>>,
+    updates: Sender<TrayMenu>,
+}
+
+impl Win32Tray {
+    pub fn spawn(menu: TrayMenu, icon: Option<image::RgbaImage>) -> Self {
+        let (action_sender, actions) = mpsc::channel();
+        let (updates, update_receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(e) = run(menu, icon, action_sender, update_receiver) {
+                error!("Tray icon unavailable: {}", e);
+            }
+        });
+        Self {
+            actions: Mutex::new(actions),
+            updates,
+        }
+    }
+}
+
+impl TrayBackend for Win32Tray {
+    fn poll(&self) -> Option<TrayAction> {
+        self.actions.lock().ok()?.try_recv().ok()
+    }
+
+    fn update(&self, menu: &TrayMenu) {
+        let _ = self.updates.send(menu.clone());
+    }
+}
+
+fn run(
+    mut menu: TrayMenu,
+    icon: Option<image::RgbaImage>,
+    actions: Sender<TrayAction>,
+    updates: Receiver<TrayMenu>,
+) -> Result<()> {
+    let mut builder = TrayIconBuilder::new()
+        .with_tooltip("pyCatAI-pet")
+        .with_menu(Box::new(build_menu(&menu)?));
+    if let Some(icon) = icon {
+        let (width, height) = icon.dimensions();
+        builder = builder.with_icon(Icon::from_rgba(icon.into_raw(), width, height)?);
+    }
+    let tray: TrayIcon = builder.build()?;
+    info!("Tray icon ready");
+
+    loop {
+        unsafe {
+            let mut message = MSG::default();
+            while PeekMessageW(&mut message, HWND(0), 0, 0, PM_REMOVE).as_bool() {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+        }
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            if let Some(action) = action_for(&event.id.0, &menu) {
+                if actions.send(action).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        while let Ok(update) = updates.try_recv() {
+            menu = update;
+            tray.set_menu(Some(Box::new(build_menu(&menu)?)));
+        }
+        std::thread::sleep(PUMP_INTERVAL);
+    }
+}
+
+// Id của mục menu: tên hành động, kèm chỉ số với persona và sprite
+fn build_menu(menu: &TrayMenu) -> Result<Menu> {
+    let personas: Vec<CheckMenuItem> = menu
+        .personas
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            CheckMenuItem::with_id(format!("persona:{}", index), name, true, index == menu.selected_persona(), None)
+        })
+        .collect();
+    let skins: Vec<CheckMenuItem> = menu
+        .skins
+        .iter()
+        .enumerate()
+        .map(|(index, dir)| {
+            CheckMenuItem::with_id(format!("skin:{}", index), tray::skin_name(dir), true, index == menu.selected_skin(), None)
+        })
+        .collect();
+    let persona_items: Vec<&dyn IsMenuItem> = personas.iter().map(|item| item as &dyn IsMenuItem).collect();
+    let skin_items: Vec<&dyn IsMenuItem> = skins.iter().map(|item| item as &dyn IsMenuItem).collect();
+
+    Menu::with_items(&[
+        &CheckMenuItem::with_id("pause", "Pause", true, menu.paused, None),
+        &CheckMenuItem::with_id("overlay", "Overlay", true, menu.overlay, None),
+        &MenuItem::with_id("comment", "Comment now", true, None),
+        &CheckMenuItem::with_id("mute", "Mute", true, menu.muted, None),
+        &Submenu::with_items("Persona", true, &persona_items)?,
+        &Submenu::with_items("Skin", true, &skin_items)?,
+        &MenuItem::with_id("settings", "Settings...", true, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id("quit", "Quit", true, None),
+    ])
+    .map_err(|e| anyhow!("Failed to build the tray menu: {}", e))
+}
+
+fn action_for(id: &str, menu: &TrayMenu) -> Option<TrayAction> {
+    let index = |prefix: &str| id.strip_prefix(prefix).and_then(|index| index.parse::<usize>().ok());
+    match id {
+        "pause" => Some(TrayAction::TogglePause),
+        "overlay" => Some(TrayAction::ToggleOverlay),
+        "comment" => Some(TrayAction::CommentNow),
+        "mute" => Some(TrayAction::ToggleMute),
+        "settings" => Some(TrayAction::Settings),
+        "quit" => Some(TrayAction::Quit),
+        _ => {
+            if let Some(name) = index("persona:").and_then(|index| menu.personas.get(index)) {
+                Some(TrayAction::Persona(name.clone()))
+            } else {
+                index("skin:").and_then(|index| menu.skins.get(index)).map(|dir| TrayAction::Skin(dir.clone()))
+            }
+        }
+    }
+}
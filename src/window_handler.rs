@@ -1,42 +1,451 @@
-// File này hiện không sử dụng. Để dự án gọn gàng, có thể xóa hoàn toàn file này
-// Nhưng tôi giữ lại một số import để tránh lỗi biên dịch nếu có tham chiếu đến nó
-// Xóa import không sử dụng 
-// use log::error;
-
-// Xóa bỏ struct và implementation không sử dụng
-// pub struct Handler;
-// 
-// impl Handler {
-//     pub fn get_foreground_window_position() -> Option<(i32, i32, i32)> {
-//         unsafe {
-//             let window = GetForegroundWindow();
-//             
-//             if window == HWND(0) {
-//                 return None;
-//             }
-//             
-//             let mut rect = windows::Win32::Foundation::RECT::default();
-//             
-//             if GetWindowRect(window, &mut rect).is_ok() {
-//                 if rect.left >= 40 && rect.top >= 50 {
-//                     return Some((rect.left, rect.top, rect.right));
-//                 }
-//             } else {
-//                 error!("Failed to get window rectangle");
-//             }
-//             
-//             None
-//         }
-//     }
-// }
-// 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     
-//     #[test]
-//     fn test_get_foreground_window_position() {
-//         let pos = Handler::get_foreground_window_position();
-//         println!("Window position: {:?}", pos);
-//     }
-// } 
\ No newline at end of file
+// Tương tác với cửa sổ của các ứng dụng khác: liệt kê cửa sổ đang hiển thị
+// và tính toán các "bậc" (mép trên cửa sổ) mà con mèo có thể đứng lên.
+// Phần gọi API của từng hệ điều hành nằm trong window_win32 và window_x11.
+use anyhow::Result;
+#[cfg(target_os = "linux")]
+use log::warn;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Hình chữ nhật của một cửa sổ trong toạ độ màn hình
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl WindowRect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    // The window covers the horizontal line at `y`
+    fn covers_row(&self, y: i32) -> bool {
+        self.top <= y && y < self.bottom
+    }
+}
+
+// Phần mép trên còn nhìn thấy của một cửa sổ, nơi con mèo có thể đi lên
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ledge {
+    pub left: i32,
+    pub right: i32,
+    pub y: i32,
+}
+
+impl Ledge {
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn contains_x(&self, x: i32) -> bool {
+        self.left <= x && x < self.right
+    }
+}
+
+// Build walkable ledges from windows listed front-to-back (z-order).
+// A window's top edge is cut wherever a window in front of it covers that row,
+// and pieces narrower than `min_width` are dropped.
+pub fn find_ledges(windows: &[WindowRect], min_width: i32) -> Vec<Ledge> {
+    let mut ledges = Vec::new();
+
+    for (index, window) in windows.iter().enumerate() {
+        if window.width() <= 0 || window.height() <= 0 {
+            continue;
+        }
+
+        let mut spans = vec![(window.left, window.right)];
+        for front in windows[..index].iter().filter(|w| w.covers_row(window.top)) {
+            spans = spans
+                .into_iter()
+                .flat_map(|(left, right)| {
+                    let mut pieces = Vec::with_capacity(2);
+                    if front.right <= left || front.left >= right {
+                        pieces.push((left, right));
+                    } else {
+                        if front.left > left {
+                            pieces.push((left, front.left));
+                        }
+                        if front.right < right {
+                            pieces.push((front.right, right));
+                        }
+                    }
+                    pieces
+                })
+                .collect();
+        }
+
+        ledges.extend(
+            spans
+                .into_iter()
+                .filter(|(left, right)| right - left >= min_width)
+                .map(|(left, right)| Ledge { left, right, y: window.top }),
+        );
+    }
+
+    ledges
+}
+
+// Rectangles covering the pixels whose alpha is at least `threshold`: one
+// run per row, merged with an identical run directly above it.
+pub fn opaque_rects(image: &image::RgbaImage, threshold: u8) -> Vec<WindowRect> {
+    let mut done = Vec::new();
+    let mut open: Vec<WindowRect> = Vec::new();
+
+    for y in 0..image.height() as i32 {
+        let mut runs = Vec::new();
+        let mut start = None;
+        for x in 0..=image.width() as i32 {
+            let opaque = x < image.width() as i32
+                && image.get_pixel(x as u32, y as u32)[3] >= threshold;
+            match (opaque, start) {
+                (true, None) => start = Some(x),
+                (false, Some(left)) => {
+                    runs.push((left, x));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        let mut next = Vec::with_capacity(runs.len());
+        for (left, right) in runs {
+            match open.iter().position(|r| r.left == left && r.right == right) {
+                Some(index) => {
+                    let mut rect = open.swap_remove(index);
+                    rect.bottom = y + 1;
+                    next.push(rect);
+                }
+                None => next.push(WindowRect::new(left, y, right, y + 1)),
+            }
+        }
+        done.append(&mut open);
+        open = next;
+    }
+
+    done.append(&mut open);
+    done
+}
+
+// Ledge supporting a cat standing at `x` with its feet near `y`
+pub fn support_at(ledges: &[Ledge], x: i32, y: i32, tolerance: i32) -> Option<Ledge> {
+    ledges
+        .iter()
+        .filter(|l| l.contains_x(x) && (l.y - y).abs() <= tolerance)
+        .min_by_key(|l| (l.y - y).abs())
+        .copied()
+}
+
+// First ledge a cat falling at `x` from `from_y` down to `to_y` would land on
+pub fn landing_between(ledges: &[Ledge], x: i32, from_y: i32, to_y: i32) -> Option<Ledge> {
+    ledges
+        .iter()
+        .filter(|l| l.contains_x(x) && l.y >= from_y && l.y <= to_y)
+        .min_by_key(|l| l.y)
+        .copied()
+}
+
+// Pick a ledge above the cat that is reachable with one jump in the walking
+// direction (`direction` is +1 for right, -1 for left). Returns the landing x
+// together with the ledge.
+pub fn jump_target(
+    ledges: &[Ledge],
+    x: i32,
+    y: i32,
+    direction: i32,
+    reach: i32,
+    max_height: i32,
+    margin: i32,
+) -> Option<(i32, Ledge)> {
+    let (near, far) = if direction >= 0 { (x, x + reach) } else { (x - reach, x) };
+
+    ledges
+        .iter()
+        .filter(|l| l.y < y && y - l.y <= max_height)
+        .filter(|l| l.width() > margin * 2)
+        .filter_map(|l| {
+            let left = (l.left + margin).max(near);
+            let right = (l.right - margin).min(far);
+            if left > right {
+                return None;
+            }
+            let landing_x = if direction >= 0 { left } else { right };
+            Some((landing_x, *l))
+        })
+        .min_by_key(|(landing_x, l)| ((landing_x - x).abs(), y - l.y))
+}
+
+// Thông tin cửa sổ đang được focus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundWindow {
+    pub title: String,
+    // Tên ứng dụng sở hữu cửa sổ: WM_CLASS trên X11, tên file .exe trên Windows
+    pub app: String,
+    pub rect: WindowRect,
+    // Covers the whole monitor, e.g. a presentation, video or game
+    pub fullscreen: bool,
+}
+
+// Platform backend for reading other applications' windows
+pub trait WindowInfo: Send + Sync {
+    // Title and bounds of the window that currently has focus
+    fn foreground_window(&self) -> Option<ForegroundWindow>;
+
+    // Visible top-level windows, front-to-back, excluding our own
+    fn visible_windows(&self) -> Vec<WindowRect>;
+
+    // Screen area not covered by taskbars and panels
+    fn work_area(&self) -> Option<WindowRect>;
+
+    // Time since the last keyboard or mouse input anywhere on the desktop,
+    // `None` when the platform cannot tell
+    fn idle_time(&self) -> Option<Duration> {
+        None
+    }
+
+    // Only let mouse input hit `region` (window coordinates) of our own window,
+    // `None` makes the whole window clickable again. `window_id` is the native
+    // handle reported by iced.
+    fn set_input_region(&self, _window_id: u64, _region: Option<&[WindowRect]>) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Chọn backend phù hợp với nền tảng đang chạy
+pub fn default_backend() -> Arc<dyn WindowInfo> {
+    #[cfg(windows)]
+    {
+        Arc::new(crate::window_win32::Win32WindowInfo)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        match crate::window_x11::X11WindowInfo::connect() {
+            Ok(backend) => Arc::new(backend),
+            Err(e) => {
+                warn!("X11 window info unavailable, cat stays on the ground: {}", e);
+                Arc::new(NullWindowInfo)
+            }
+        }
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        Arc::new(NullWindowInfo)
+    }
+}
+
+// Backend không có cửa sổ nào, dùng khi nền tảng không được hỗ trợ
+pub struct NullWindowInfo;
+
+impl WindowInfo for NullWindowInfo {
+    fn foreground_window(&self) -> Option<ForegroundWindow> {
+        None
+    }
+
+    fn visible_windows(&self) -> Vec<WindowRect> {
+        Vec::new()
+    }
+
+    fn work_area(&self) -> Option<WindowRect> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FakeWindows {
+    foreground: Option<ForegroundWindow>,
+    windows: Vec<WindowRect>,
+    work_area: Option<WindowRect>,
+    idle: Option<Duration>,
+}
+
+// Backend giả lập với danh sách cửa sổ tự đặt, dùng cho test và chạy
+// mô phỏng không cửa sổ
+#[derive(Default)]
+pub struct FakeWindowInfo {
+    state: std::sync::Mutex<FakeWindows>,
+}
+
+impl FakeWindowInfo {
+    pub fn new(work_area: WindowRect) -> Self {
+        let info = Self::default();
+        info.set_work_area(work_area);
+        info
+    }
+
+    pub fn set_windows(&self, windows: Vec<WindowRect>) {
+        self.state.lock().unwrap().windows = windows;
+    }
+
+    #[cfg(test)]
+    pub fn set_foreground(&self, foreground: Option<ForegroundWindow>) {
+        self.state.lock().unwrap().foreground = foreground;
+    }
+
+    pub fn set_work_area(&self, work_area: WindowRect) {
+        self.state.lock().unwrap().work_area = Some(work_area);
+    }
+
+    #[cfg(test)]
+    pub fn set_idle(&self, idle: Option<Duration>) {
+        self.state.lock().unwrap().idle = idle;
+    }
+}
+
+impl WindowInfo for FakeWindowInfo {
+    fn foreground_window(&self) -> Option<ForegroundWindow> {
+        self.state.lock().unwrap().foreground.clone()
+    }
+
+    fn visible_windows(&self) -> Vec<WindowRect> {
+        self.state.lock().unwrap().windows.clone()
+    }
+
+    fn work_area(&self) -> Option<WindowRect> {
+        self.state.lock().unwrap().work_area
+    }
+
+    fn idle_time(&self) -> Option<Duration> {
+        self.state.lock().unwrap().idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_window_ledge() {
+        let windows = [WindowRect::new(100, 300, 700, 800)];
+        let ledges = find_ledges(&windows, 50);
+        assert_eq!(ledges, vec![Ledge { left: 100, right: 700, y: 300 }]);
+    }
+
+    #[test]
+    fn test_front_window_splits_ledge() {
+        // Cửa sổ phía trước che phần giữa mép trên của cửa sổ phía sau
+        let windows = [
+            WindowRect::new(300, 200, 400, 600),
+            WindowRect::new(100, 300, 700, 800),
+        ];
+        let ledges = find_ledges(&windows, 50);
+        assert_eq!(
+            ledges,
+            vec![
+                Ledge { left: 300, right: 400, y: 200 },
+                Ledge { left: 100, right: 300, y: 300 },
+                Ledge { left: 400, right: 700, y: 300 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_narrow_and_hidden_ledges_dropped() {
+        let windows = [
+            WindowRect::new(0, 0, 1000, 1000),
+            WindowRect::new(100, 300, 700, 800),
+            WindowRect::new(1000, 500, 1030, 600),
+        ];
+        let ledges = find_ledges(&windows, 50);
+        assert_eq!(ledges, vec![Ledge { left: 0, right: 1000, y: 0 }]);
+    }
+
+    #[test]
+    fn test_window_below_edge_does_not_cut() {
+        // Cửa sổ phía trước nằm hoàn toàn bên dưới mép trên thì không che
+        let windows = [
+            WindowRect::new(200, 500, 400, 700),
+            WindowRect::new(100, 300, 700, 800),
+        ];
+        let ledges = find_ledges(&windows, 50);
+        assert!(ledges.contains(&Ledge { left: 100, right: 700, y: 300 }));
+    }
+
+    #[test]
+    fn test_opaque_rects_merge_rows() {
+        let mut image = image::RgbaImage::new(6, 4);
+        // Khối 2x2 ở góc trên trái và một dòng ở hàng cuối
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 3), (3, 3), (4, 3)] {
+            image.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+        }
+        // Pixel gần như trong suốt không được tính
+        image.put_pixel(5, 0, image::Rgba([255, 255, 255, 10]));
+
+        let mut rects = opaque_rects(&image, 128);
+        rects.sort_by_key(|r| (r.top, r.left));
+        assert_eq!(
+            rects,
+            vec![WindowRect::new(0, 0, 2, 2), WindowRect::new(2, 3, 5, 4)]
+        );
+    }
+
+    #[test]
+    fn test_support_and_landing() {
+        let ledges = [
+            Ledge { left: 100, right: 700, y: 300 },
+            Ledge { left: 500, right: 900, y: 600 },
+        ];
+
+        assert_eq!(support_at(&ledges, 150, 304, 8), Some(ledges[0]));
+        assert_eq!(support_at(&ledges, 750, 300, 8), None);
+
+        // Rơi từ trên xuống gặp bậc cao nhất trước
+        assert_eq!(landing_between(&ledges, 600, 0, 1000), Some(ledges[0]));
+        assert_eq!(landing_between(&ledges, 600, 301, 1000), Some(ledges[1]));
+        assert_eq!(landing_between(&ledges, 950, 0, 1000), None);
+    }
+
+    #[test]
+    fn test_fake_backend() {
+        let info = FakeWindowInfo::new(WindowRect::new(0, 0, 1280, 720));
+        assert_eq!(info.work_area(), Some(WindowRect::new(0, 0, 1280, 720)));
+        assert!(info.visible_windows().is_empty());
+        assert_eq!(info.foreground_window(), None);
+
+        let editor = WindowRect::new(100, 300, 700, 700);
+        info.set_windows(vec![editor]);
+        info.set_foreground(Some(ForegroundWindow {
+            title: "main.rs - editor".to_string(),
+            app: "code".to_string(),
+            rect: editor,
+            fullscreen: false,
+        }));
+
+        assert_eq!(find_ledges(&info.visible_windows(), 50).len(), 1);
+        assert_eq!(info.foreground_window().unwrap().title, "main.rs - editor");
+    }
+
+    #[test]
+    fn test_jump_target_in_direction() {
+        let ledges = [
+            Ledge { left: 300, right: 700, y: 800 },
+            Ledge { left: 0, right: 200, y: 850 },
+        ];
+
+        // Nhảy sang phải lên cửa sổ phía trước
+        assert_eq!(
+            jump_target(&ledges, 250, 1000, 1, 150, 250, 20),
+            Some((320, ledges[0]))
+        );
+
+        // Quá cao thì không nhảy được
+        assert_eq!(jump_target(&ledges, 250, 1100, 1, 150, 250, 20), None);
+
+        // Nhảy sang trái chọn bậc bên trái
+        assert_eq!(
+            jump_target(&ledges, 250, 1000, -1, 150, 250, 20),
+            Some((180, ledges[1]))
+        );
+    }
+}
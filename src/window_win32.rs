@@ -0,0 +1,247 @@
+// Backend WindowInfo cho Windows, dùng Win32 API
+use anyhow::{anyhow, Result};
+use log::error;
+use std::time::Duration;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT};
+use windows::Win32::Graphics::Dwm::{
+    DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS,
+};
+use windows::Win32::Graphics::Gdi::{
+    CombineRgn, CreateRectRgn, DeleteObject, GetMonitorInfoW, MonitorFromWindow, SetWindowRgn, HRGN,
+    MONITORINFO, MONITOR_DEFAULTTONEAREST, RGN_OR,
+};
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::System::Threading::{
+    GetCurrentProcessId, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetClassNameW, GetForegroundWindow, GetWindowLongW, GetWindowRect, GetWindowTextLengthW,
+    GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindowVisible, SystemParametersInfoW, GWL_EXSTYLE,
+    SPI_GETWORKAREA, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WS_EX_TOOLWINDOW,
+};
+
+use crate::window_handler::{ForegroundWindow, WindowInfo, WindowRect};
+
+pub struct Win32WindowInfo;
+
+impl WindowInfo for Win32WindowInfo {
+    fn foreground_window(&self) -> Option<ForegroundWindow> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd == HWND(0) {
+                return None;
+            }
+
+            let rect = window_rect(hwnd)?;
+            Some(ForegroundWindow {
+                title: window_title(hwnd),
+                app: process_name(hwnd),
+                rect,
+                fullscreen: is_fullscreen(hwnd, rect),
+            })
+        }
+    }
+
+    fn visible_windows(&self) -> Vec<WindowRect> {
+        let mut windows: Vec<WindowRect> = Vec::new();
+        unsafe {
+            if let Err(e) = EnumWindows(Some(collect), LPARAM(&mut windows as *mut _ as isize)) {
+                error!("Failed to enumerate windows: {}", e);
+            }
+        }
+        windows
+    }
+
+    fn work_area(&self) -> Option<WindowRect> {
+        let mut rect = RECT::default();
+        unsafe {
+            if let Err(e) = SystemParametersInfoW(
+                SPI_GETWORKAREA,
+                0,
+                Some(&mut rect as *mut RECT as *mut _),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            ) {
+                error!("Failed to read work area: {}", e);
+                return None;
+            }
+        }
+        Some(WindowRect::new(rect.left, rect.top, rect.right, rect.bottom))
+    }
+
+    fn idle_time(&self) -> Option<Duration> {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            ..Default::default()
+        };
+        unsafe {
+            if !GetLastInputInfo(&mut info).as_bool() {
+                return None;
+            }
+            // Cả hai đều tính bằng mili giây kể từ lúc bật máy, quay vòng sau 49 ngày
+            Some(Duration::from_millis(GetTickCount().wrapping_sub(info.dwTime) as u64))
+        }
+    }
+
+    fn set_input_region(&self, window_id: u64, region: Option<&[WindowRect]>) -> Result<()> {
+        // winit reports the HWND as the window id on Windows
+        let hwnd = HWND(window_id as isize);
+
+        unsafe {
+            // A window region also clips drawing, which is what we want for a
+            // transparent overlay: nothing outside the region is visible anyway
+            let rgn = match region {
+                None => HRGN(0),
+                Some(rects) => {
+                    let rgn = CreateRectRgn(0, 0, 0, 0);
+                    for rect in rects {
+                        let part = CreateRectRgn(rect.left, rect.top, rect.right, rect.bottom);
+                        CombineRgn(rgn, rgn, part, RGN_OR);
+                        DeleteObject(part);
+                    }
+                    rgn
+                }
+            };
+
+            // The system owns the region after a successful call
+            if SetWindowRgn(hwnd, rgn, BOOL::from(true)) == 0 {
+                if rgn != HRGN(0) {
+                    DeleteObject(rgn);
+                }
+                return Err(anyhow!("SetWindowRgn failed"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// EnumWindows callback, gọi theo thứ tự z (cửa sổ trên cùng trước)
+unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam.0 as *mut Vec<WindowRect>);
+
+    // Chỉ cửa sổ ứng dụng thật; desktop cũng có tiêu đề và phủ kín màn hình
+    // nên sẽ thành một mép ở y=0
+    if !IsWindowVisible(hwnd).as_bool()
+        || IsIconic(hwnd).as_bool()
+        || GetWindowTextLengthW(hwnd) == 0
+        || is_desktop(hwnd)
+        || is_tool_window(hwnd)
+        || is_cloaked(hwnd)
+    {
+        return true.into();
+    }
+
+    // Bỏ qua cửa sổ của chính con mèo
+    let mut pid = 0u32;
+    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+    if pid == GetCurrentProcessId() {
+        return true.into();
+    }
+
+    if let Some(rect) = window_rect(hwnd) {
+        windows.push(rect);
+    }
+
+    true.into()
+}
+
+// Cửa sổ bị DWM ẩn (ứng dụng UWP chạy nền) vẫn báo là visible
+unsafe fn is_cloaked(hwnd: HWND) -> bool {
+    let mut cloaked = 0u32;
+    DwmGetWindowAttribute(
+        hwnd,
+        DWMWA_CLOAKED,
+        &mut cloaked as *mut u32 as *mut _,
+        std::mem::size_of::<u32>() as u32,
+    )
+    .is_ok()
+        && cloaked != 0
+}
+
+// Thanh công cụ nổi, bảng màu... không có trên taskbar
+unsafe fn is_tool_window(hwnd: HWND) -> bool {
+    GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW.0 != 0
+}
+
+// Cửa sổ desktop (hình nền và biểu tượng)
+unsafe fn is_desktop(hwnd: HWND) -> bool {
+    let mut class = [0u16; 64];
+    let length = GetClassNameW(hwnd, &mut class);
+    let class = String::from_utf16_lossy(&class[..length.max(0) as usize]);
+    class == "Progman" || class == "WorkerW"
+}
+
+unsafe fn window_rect(hwnd: HWND) -> Option<WindowRect> {
+    // Extended frame bounds exclude the invisible resize border
+    let mut rect = RECT::default();
+    let bounds = DwmGetWindowAttribute(
+        hwnd,
+        DWMWA_EXTENDED_FRAME_BOUNDS,
+        &mut rect as *mut RECT as *mut _,
+        std::mem::size_of::<RECT>() as u32,
+    );
+    if bounds.is_err() && GetWindowRect(hwnd, &mut rect).is_err() {
+        return None;
+    }
+
+    if rect.right > rect.left && rect.bottom > rect.top {
+        Some(WindowRect::new(rect.left, rect.top, rect.right, rect.bottom))
+    } else {
+        None
+    }
+}
+
+// Phủ kín màn hình chứa nó; bỏ qua desktop, vốn cũng phủ kín màn hình
+unsafe fn is_fullscreen(hwnd: HWND, rect: WindowRect) -> bool {
+    if is_desktop(hwnd) {
+        return false;
+    }
+
+    let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+        return false;
+    }
+    let screen = info.rcMonitor;
+    rect == WindowRect::new(screen.left, screen.top, screen.right, screen.bottom)
+}
+
+unsafe fn window_title(hwnd: HWND) -> String {
+    let length = GetWindowTextLengthW(hwnd);
+    if length <= 0 {
+        return String::new();
+    }
+
+    let mut buffer = vec![0u16; length as usize + 1];
+    let copied = GetWindowTextW(hwnd, &mut buffer);
+    String::from_utf16_lossy(&buffer[..copied.max(0) as usize])
+}
+
+// Tên file thực thi của tiến trình sở hữu cửa sổ, ví dụ "chrome" cho chrome.exe
+unsafe fn process_name(hwnd: HWND) -> String {
+    let mut pid = 0u32;
+    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+    let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, BOOL::from(false), pid) else {
+        return String::new();
+    };
+
+    let mut buffer = [0u16; 1024];
+    let mut length = buffer.len() as u32;
+    let queried = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut length);
+    let _ = CloseHandle(process);
+    if queried.is_err() {
+        return String::new();
+    }
+
+    let path = String::from_utf16_lossy(&buffer[..length as usize]);
+    std::path::Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
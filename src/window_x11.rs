@@ -0,0 +1,369 @@
+// Backend WindowInfo cho Linux, đọc thông tin cửa sổ qua X11/EWMH
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::cookie::Cookie;
+use x11rb::protocol::screensaver::ConnectionExt as _;
+use x11rb::protocol::shape::{ConnectionExt as _, SK, SO};
+use x11rb::protocol::xproto::{
+    AtomEnum, ClipOrdering, ConnectionExt, GetGeometryReply, GetPropertyReply, GetWindowAttributesReply, MapState,
+    Rectangle, TranslateCoordinatesReply, Window,
+};
+use x11rb::rust_connection::RustConnection;
+
+use crate::window_handler::{ForegroundWindow, WindowInfo, WindowRect};
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST_STACKING,
+        _NET_CURRENT_DESKTOP,
+        _NET_FRAME_EXTENTS,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_WM_WINDOW_TYPE_SPLASH,
+        _NET_WORKAREA,
+        UTF8_STRING,
+    }
+}
+
+impl Atoms {
+    // Desktop phủ kín màn hình, panel, màn hình chờ và thông báo: không phải
+    // cửa sổ ứng dụng để mèo leo lên
+    fn skipped_types(&self) -> [u32; 4] {
+        [
+            self._NET_WM_WINDOW_TYPE_DESKTOP,
+            self._NET_WM_WINDOW_TYPE_DOCK,
+            self._NET_WM_WINDOW_TYPE_NOTIFICATION,
+            self._NET_WM_WINDOW_TYPE_SPLASH,
+        ]
+    }
+}
+
+fn values(cookie: Cookie<'_, RustConnection, GetPropertyReply>) -> Result<Vec<u32>> {
+    let reply = cookie.reply()?;
+    Ok(reply.value32().map(|values| values.collect()).unwrap_or_default())
+}
+
+fn frame_from(cookies: FrameCookies) -> Result<WindowRect> {
+    let geometry = cookies.geometry.reply()?;
+    let origin = cookies.origin.reply()?;
+    let extents = values(cookies.extents)?;
+    let (left, right, top, bottom) = match extents.as_slice() {
+        [left, right, top, bottom] => (*left as i32, *right as i32, *top as i32, *bottom as i32),
+        _ => (0, 0, 0, 0),
+    };
+
+    let x = origin.dst_x as i32;
+    let y = origin.dst_y as i32;
+    Ok(WindowRect::new(
+        x - left,
+        y - top,
+        x + geometry.width as i32 + right,
+        y + geometry.height as i32 + bottom,
+    ))
+}
+
+// Cửa sổ có một trong các loại `skipped` của _NET_WM_WINDOW_TYPE
+fn has_type(types: &[u32], skipped: &[u32]) -> bool {
+    types.iter().any(|kind| skipped.contains(kind))
+}
+
+// Request đã gửi cho một cửa sổ, đọc trả lời sau
+struct FrameCookies<'c> {
+    geometry: Cookie<'c, RustConnection, GetGeometryReply>,
+    origin: Cookie<'c, RustConnection, TranslateCoordinatesReply>,
+    extents: Cookie<'c, RustConnection, GetPropertyReply>,
+}
+
+struct WindowCookies<'c> {
+    attributes: Cookie<'c, RustConnection, GetWindowAttributesReply>,
+    state: Cookie<'c, RustConnection, GetPropertyReply>,
+    types: Cookie<'c, RustConnection, GetPropertyReply>,
+    pid: Cookie<'c, RustConnection, GetPropertyReply>,
+    frame: FrameCookies<'c>,
+}
+
+pub struct X11WindowInfo {
+    conn: RustConnection,
+    root: Window,
+    root_size: (i32, i32),
+    atoms: Atoms,
+}
+
+impl X11WindowInfo {
+    pub fn connect() -> Result<Self> {
+        let (conn, screen_num) = RustConnection::connect(None)?;
+        let screen = &conn.setup().roots[screen_num];
+        let root = screen.root;
+        let root_size = (screen.width_in_pixels as i32, screen.height_in_pixels as i32);
+        let atoms = Atoms::new(&conn)?.reply()?;
+
+        Ok(Self {
+            conn,
+            root,
+            root_size,
+            atoms,
+        })
+    }
+
+    fn property(
+        &self,
+        window: Window,
+        property: u32,
+        kind: impl Into<u32>,
+    ) -> Result<Cookie<'_, RustConnection, GetPropertyReply>> {
+        Ok(self.conn.get_property(false, window, property, kind, 0, u32::MAX / 4)?)
+    }
+
+    fn cardinals(&self, window: Window, property: u32, kind: impl Into<u32>) -> Result<Vec<u32>> {
+        values(self.property(window, property, kind)?)
+    }
+
+    fn title(&self, window: Window) -> String {
+        let utf8 = self
+            .conn
+            .get_property(false, window, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING, 0, 1024)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .filter(|reply| !reply.value.is_empty());
+
+        // Fall back to the ICCCM title for clients without EWMH names
+        let reply = utf8.or_else(|| {
+            self.conn
+                .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+        });
+
+        reply
+            .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+            .unwrap_or_default()
+    }
+
+    // Class part of WM_CLASS ("Firefox", "Code"), which stays the same while
+    // the title changes with every tab or document
+    fn app(&self, window: Window) -> String {
+        let class = self
+            .conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.value)
+            .unwrap_or_default();
+
+        // Hai chuỗi kết thúc bằng NUL: instance rồi class
+        class
+            .split(|byte| *byte == 0)
+            .rfind(|part| !part.is_empty())
+            .map(|part| String::from_utf8_lossy(part).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn request_frame(&self, window: Window) -> Result<FrameCookies<'_>> {
+        Ok(FrameCookies {
+            geometry: self.conn.get_geometry(window)?,
+            origin: self.conn.translate_coordinates(window, self.root, 0, 0)?,
+            extents: self.property(window, self.atoms._NET_FRAME_EXTENTS, AtomEnum::CARDINAL)?,
+        })
+    }
+
+    // Bounds of a client window including the frame drawn by the window manager
+    fn frame_rect(&self, window: Window) -> Result<WindowRect> {
+        frame_from(self.request_frame(window)?)
+    }
+
+    fn request_window(&self, window: Window) -> Result<WindowCookies<'_>> {
+        Ok(WindowCookies {
+            attributes: self.conn.get_window_attributes(window)?,
+            state: self.property(window, self.atoms._NET_WM_STATE, AtomEnum::ATOM)?,
+            types: self.property(window, self.atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM)?,
+            pid: self.property(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?,
+            frame: self.request_frame(window)?,
+        })
+    }
+
+    // Khung của cửa sổ, None khi nó không hiện ra hoặc không phải cửa sổ ứng dụng
+    fn visible_frame(&self, cookies: WindowCookies) -> Result<Option<WindowRect>> {
+        let attributes = cookies.attributes.reply()?;
+        if attributes.map_state != MapState::VIEWABLE {
+            return Ok(None);
+        }
+
+        let state = values(cookies.state)?;
+        if state.contains(&self.atoms._NET_WM_STATE_HIDDEN) {
+            return Ok(None);
+        }
+
+        let types = values(cookies.types)?;
+        if has_type(&types, &self.atoms.skipped_types()) {
+            return Ok(None);
+        }
+
+        // Bỏ qua cửa sổ của chính con mèo
+        let pid = values(cookies.pid)?;
+        if pid.first() == Some(&std::process::id()) {
+            return Ok(None);
+        }
+
+        frame_from(cookies.frame).map(Some)
+    }
+
+    // Cửa sổ ở trạng thái fullscreen của EWMH, hoặc phủ kín màn hình
+    fn is_fullscreen(&self, window: Window, rect: WindowRect) -> bool {
+        let state = self
+            .cardinals(window, self.atoms._NET_WM_STATE, AtomEnum::ATOM)
+            .unwrap_or_default();
+        state.contains(&self.atoms._NET_WM_STATE_FULLSCREEN)
+            || rect == WindowRect::new(0, 0, self.root_size.0, self.root_size.1)
+    }
+
+    fn active_window(&self) -> Result<Window> {
+        let active = self.cardinals(self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)?;
+        active
+            .first()
+            .copied()
+            .filter(|window| *window != 0)
+            .ok_or_else(|| anyhow!("No active window"))
+    }
+}
+
+impl WindowInfo for X11WindowInfo {
+    fn foreground_window(&self) -> Option<ForegroundWindow> {
+        let window = self.active_window().ok()?;
+        match self.frame_rect(window) {
+            Ok(rect) => Some(ForegroundWindow {
+                title: self.title(window),
+                app: self.app(window),
+                rect,
+                fullscreen: self.is_fullscreen(window, rect),
+            }),
+            Err(e) => {
+                debug!("Failed to read active window geometry: {}", e);
+                None
+            }
+        }
+    }
+
+    fn visible_windows(&self) -> Vec<WindowRect> {
+        // _NET_CLIENT_LIST_STACKING is ordered bottom-to-top
+        let stacking = match self.cardinals(self.root, self.atoms._NET_CLIENT_LIST_STACKING, AtomEnum::WINDOW) {
+            Ok(stacking) => stacking,
+            Err(e) => {
+                debug!("Failed to read client stacking list: {}", e);
+                return Vec::new();
+            }
+        };
+
+        // Gửi request cho mọi cửa sổ rồi mới chờ trả lời: một lượt đi về với
+        // X server mỗi lần, thay vì vài lượt cho từng cửa sổ
+        let requests: Vec<_> = stacking.into_iter().rev().map(|window| self.request_window(window)).collect();
+        requests
+            .into_iter()
+            .filter_map(|cookies| cookies.and_then(|cookies| self.visible_frame(cookies)).ok().flatten())
+            .filter(|rect| rect.width() > 0 && rect.height() > 0)
+            .collect()
+    }
+
+    fn work_area(&self) -> Option<WindowRect> {
+        let desktop = self
+            .cardinals(self.root, self.atoms._NET_CURRENT_DESKTOP, AtomEnum::CARDINAL)
+            .ok()
+            .and_then(|values| values.first().copied())
+            .unwrap_or(0) as usize;
+
+        // _NET_WORKAREA holds x, y, width, height for every desktop
+        let areas = self
+            .cardinals(self.root, self.atoms._NET_WORKAREA, AtomEnum::CARDINAL)
+            .unwrap_or_default();
+        if let Some(area) = areas.chunks_exact(4).nth(desktop) {
+            let (x, y, width, height) = (area[0] as i32, area[1] as i32, area[2] as i32, area[3] as i32);
+            return Some(WindowRect::new(x, y, x + width, y + height));
+        }
+
+        Some(WindowRect::new(0, 0, self.root_size.0, self.root_size.1))
+    }
+
+    // Cần extension MIT-SCREEN-SAVER, có sẵn trên hầu hết X server
+    fn idle_time(&self) -> Option<Duration> {
+        let query = || -> Result<u32> { Ok(self.conn.screensaver_query_info(self.root)?.reply()?.ms_since_user_input) };
+        match query() {
+            Ok(ms) => Some(Duration::from_millis(ms as u64)),
+            Err(e) => {
+                debug!("Failed to read idle time: {}", e);
+                None
+            }
+        }
+    }
+
+    fn set_input_region(&self, window_id: u64, region: Option<&[WindowRect]>) -> Result<()> {
+        let window = window_id as Window;
+
+        // Input shape only: drawing is untouched, clicks outside fall through
+        match region {
+            Some(rects) => {
+                let rectangles: Vec<Rectangle> = rects
+                    .iter()
+                    .map(|r| Rectangle {
+                        x: r.left as i16,
+                        y: r.top as i16,
+                        width: r.width().max(0) as u16,
+                        height: r.height().max(0) as u16,
+                    })
+                    .collect();
+                self.conn.shape_rectangles(
+                    SO::SET,
+                    SK::INPUT,
+                    ClipOrdering::UNSORTED,
+                    window,
+                    0,
+                    0,
+                    &rectangles,
+                )?;
+            }
+            None => {
+                self.conn.shape_mask(SO::SET, SK::INPUT, window, 0, 0, x11rb::NONE)?;
+            }
+        }
+
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handler::{find_ledges, Ledge};
+
+    #[test]
+    fn test_desktop_and_panels_are_not_ledges() {
+        // Số atom bất kỳ, chỉ cần khác nhau
+        let (normal, desktop, dock) = (1, 2, 3);
+        let skipped = [desktop, dock, 4, 5];
+
+        // Trên cùng trước: panel dọc, một cửa sổ, rồi desktop bằng cả màn hình
+        let windows = [
+            (WindowRect::new(0, 200, 64, 880), vec![dock]),
+            (WindowRect::new(300, 400, 900, 700), vec![normal]),
+            (WindowRect::new(0, 0, 1920, 1080), vec![desktop]),
+        ];
+        let all: Vec<WindowRect> = windows.iter().map(|(rect, _)| *rect).collect();
+        assert!(find_ledges(&all, 50).contains(&Ledge { left: 0, right: 1920, y: 0 }));
+
+        let kept: Vec<WindowRect> = windows
+            .iter()
+            .filter(|(_, types)| !has_type(types, &skipped))
+            .map(|(rect, _)| *rect)
+            .collect();
+        assert_eq!(find_ledges(&kept, 50), vec![Ledge { left: 300, right: 900, y: 400 }]);
+    }
+}